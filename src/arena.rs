@@ -0,0 +1,169 @@
+use macroquad::prelude::*;
+
+use crate::{
+    assets::World,
+    enemy::{ENEMIES, Enemy},
+    inventory::LockerItem,
+    player::Player,
+    settings::settings,
+};
+
+/// Seconds between clearing a wave and the next one spawning
+pub const WAVE_DELAY: f32 = 4.0;
+/// Enemies don't spawn closer than this to the player
+const SPAWN_DISTANCE: f32 = 96.0;
+#[cfg(not(target_arch = "wasm32"))]
+const HIGHSCORE_PATH: &str = "highscore.txt";
+
+/// Best run in arena mode, as the wave reached and seconds survived
+#[derive(Clone, Copy)]
+pub struct Highscore {
+    pub wave: u32,
+    pub time: f32,
+}
+impl Highscore {
+    fn beats(&self, other: &Highscore) -> bool {
+        self.wave > other.wave || (self.wave == other.wave && self.time > other.time)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Option<Self> {
+        let text = std::fs::read_to_string(HIGHSCORE_PATH).ok()?;
+        let (wave, time) = text.trim().split_once(' ')?;
+        Some(Self {
+            wave: wave.parse().ok()?,
+            time: time.parse().ok()?,
+        })
+    }
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Option<Self> {
+        let wave = unsafe { highscore_wave() };
+        Some(Self {
+            wave: wave.try_into().ok()?,
+            time: unsafe { highscore_time() },
+        })
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self) {
+        let _ = std::fs::write(HIGHSCORE_PATH, format!("{} {}", self.wave, self.time));
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self) {
+        unsafe { highscore_save(self.wave, self.time) }
+    }
+}
+// kept in the browser's local storage by the plugin in `web/index.html`
+#[cfg(target_arch = "wasm32")]
+unsafe extern "C" {
+    /// Wave of the saved highscore, or -1 if there is none
+    fn highscore_wave() -> i32;
+    fn highscore_time() -> f32;
+    fn highscore_save(wave: u32, time: f32);
+}
+
+/// Score-attack mode, where waves of enemies keep spawning with a growing difficulty budget
+pub struct Arena {
+    pub wave: u32,
+    pub time: f32,
+    /// Seconds left until the next wave spawns
+    pub next_wave: f32,
+    pub best: Option<Highscore>,
+    pub over: bool,
+    /// What every locker held at the start, to refill them with between waves
+    lockers: Vec<Option<LockerItem>>,
+    /// Centers of the open floor tiles enemies can spawn on
+    floor: Vec<Vec2>,
+}
+impl Arena {
+    pub fn new(world: &World) -> Self {
+        let mut floor = Vec::new();
+        for chunk in &world.background {
+            for (index, tile) in chunk.tiles.iter().enumerate() {
+                let pos = vec2(
+                    ((index % 16) as i16 + chunk.x) as f32,
+                    ((index / 16) as i16 + chunk.y) as f32,
+                ) * 16.0
+                    + 8.0;
+                if *tile != 0 && !world.is_solid(pos) {
+                    floor.push(pos);
+                }
+            }
+        }
+        Self {
+            wave: 0,
+            time: 0.0,
+            next_wave: WAVE_DELAY,
+            best: Highscore::load(),
+            over: false,
+            lockers: world.lockers.iter().map(|f| f.1).collect(),
+            floor,
+        }
+    }
+    pub fn update(
+        &mut self,
+        delta_time: f32,
+        player: &Player,
+        world: &mut World,
+        enemies: &mut Vec<Enemy>,
+    ) {
+        if self.over {
+            return;
+        }
+        if player.health <= 0.0 {
+            self.over = true;
+            let score = Highscore {
+                wave: self.wave,
+                time: self.time,
+            };
+            // runs with invincibility on don't count
+            if !settings().invincible && self.best.is_none_or(|best| score.beats(&best)) {
+                score.save();
+                self.best = Some(score);
+            }
+            return;
+        }
+        self.time += delta_time;
+        if !enemies.is_empty() {
+            return;
+        }
+        self.next_wave -= delta_time;
+        if self.next_wave > 0.0 {
+            return;
+        }
+        self.next_wave = WAVE_DELAY;
+        self.wave += 1;
+        for ((_, slot), item) in world.lockers.iter_mut().zip(&self.lockers) {
+            *slot = *item;
+        }
+
+        let mut budget = settings().spawn_count(2 + self.wave * 3);
+        loop {
+            let affordable: Vec<_> = ENEMIES
+                .iter()
+                .filter(|f| f.cost > 0 && f.cost <= budget)
+                .collect();
+            if affordable.is_empty() {
+                break;
+            }
+            let Some(pos) = self.spawn_position(player) else {
+                break;
+            };
+            let ty = affordable[rand::gen_range(0, affordable.len())];
+            budget -= ty.cost;
+            let mut enemy = Enemy::new(ty, pos);
+            enemy.roll_elite(world.elite_chance);
+            enemy.hear(player.pos);
+            enemies.push(enemy);
+        }
+    }
+    /// Picks a floor tile away from the player, or `None` if the map has no floor
+    fn spawn_position(&self, player: &Player) -> Option<Vec2> {
+        let mut pos = *self.floor.first()?;
+        for _ in 0..32 {
+            pos = self.floor[rand::gen_range(0, self.floor.len())];
+            if pos.distance(player.pos) > SPAWN_DISTANCE {
+                break;
+            }
+        }
+        Some(pos)
+    }
+}
@@ -1,11 +1,24 @@
-use std::{collections::HashMap, iter::Map};
+use std::{borrow::Borrow, collections::HashMap, iter::Map};
 
 use asefile::{self, AsepriteFile};
 use image::EncodableLayout;
 use macroquad::prelude::*;
 
 use crate::{
-    player::{WEAPONS, Weapon},
+    companions::Companion,
+    elites::ELITE_CHANCE,
+    entities::{MapObjects, object_companion, object_keycard, object_tile},
+    graphics,
+    inventory::LockerItem,
+    lighting::Light,
+    modifiers::WeaponModifier,
+    particles::{GLASS_SHARDS, Particles},
+    pickups::{Pickup, PickupKind},
+    player::WEAPONS,
+    rooms::Room,
+    tile_entities::{
+        BREACH, BREACH_RANGE, Destructible, TileEntity, kind_for_tile, place_object, place_wiring,
+    },
     utils::*,
 };
 
@@ -20,6 +33,8 @@ pub struct Assets {
     pub escape_pod_tooltip: Texture2D,
     pub healthbar: Texture2D,
     pub win: Texture2D,
+    /// Palette swap for elite enemies, if shaders are supported
+    pub palette: Option<Material>,
 }
 impl Default for Assets {
     fn default() -> Self {
@@ -40,6 +55,7 @@ impl Default for Assets {
             ),
             healthbar: load_ase_texture(include_bytes!("../assets/healthbar.ase"), None),
             win: load_ase_texture(include_bytes!("../assets/win.ase"), None),
+            palette: graphics::palette_material(),
         }
     }
 }
@@ -171,40 +187,6 @@ impl StarsBackground {
         }
     }
 }
-pub enum TileEntityUpdateResult {
-    None,
-}
-type TileEntityDraw = &'static dyn Fn(&mut TileEntity, &Assets, Vec2) -> TileEntityUpdateResult;
-#[derive(Clone, Copy)]
-pub struct TileEntity {
-    pub collision: bool,
-    pub enabled: bool,
-    pub draw: TileEntityDraw,
-    pub tile_index: i16,
-}
-pub const BARRIER: TileEntity = TileEntity {
-    collision: true,
-    enabled: true,
-    tile_index: 0,
-    draw: &|this, assets, pos| {
-        assets.tileset.draw_tile(
-            pos.x,
-            pos.y,
-            (this.tile_index % 16) as f32,
-            (this.tile_index / 16) as f32,
-            None,
-        );
-        TileEntityUpdateResult::None
-    },
-};
-impl TileEntity {
-    pub fn instantiate(&self, tile_index: i16) -> Self {
-        let mut new = *self;
-        new.tile_index = tile_index;
-        new
-    }
-}
-
 pub struct World {
     pub collision: Vec<Chunk>,
     pub details: Vec<Chunk>,
@@ -212,8 +194,26 @@ pub struct World {
     pub background_details: Vec<Chunk>,
     pub interactable: Vec<Chunk>,
 
-    pub lockers: Vec<(Vec2, Option<&'static Weapon>)>,
+    pub lockers: Vec<(Vec2, Option<LockerItem>)>,
+    pub pickups: Vec<Pickup>,
+    /// Drones and survivors placed in the "Entities" layer
+    pub companions: Vec<Companion>,
     pub tile_entities: HashMap<(i16, i16), TileEntity>,
+    /// Health left of damaged destructible collision tiles
+    tile_health: HashMap<(i16, i16), f32>,
+    /// Collision tiles and tile entities broken since the world was last redrawn
+    pub broken: Vec<(i16, i16)>,
+    pub lights: Vec<Light>,
+    pub rooms: Vec<Room>,
+    pub objects: MapObjects,
+    /// Spawn, pod door and pod positions placed in the "Entities" layer, by their tile index
+    pub markers: HashMap<i16, Vec2>,
+    /// Polylines from the "Paths" object layer for enemies to patrol along
+    pub patrols: Vec<Vec<Vec2>>,
+    /// Brightness of unlit areas, from 0 (pitch black) to 1 (fully lit)
+    pub ambient: f32,
+    /// Chance of each enemy spawning as an elite
+    pub elite_chance: f32,
 
     pub x_min: i16,
     pub x_max: i16,
@@ -221,10 +221,30 @@ pub struct World {
     pub y_max: i16,
 }
 
-fn get_tile(chunks: &[&Chunk], x: i16, y: i16) -> i16 {
+/// Speed that open breaches pull things at, right next to them
+const BREACH_PULL: f32 = 120.0;
+/// Window panels in the face of walls, which shatter when shot. The row of wall above them stays.
+const GLASS_TILES: [i16; 6] = [34, 35, 36, 50, 51, 52];
+const GLASS: Destructible = Destructible {
+    health: 6.0,
+    debris: &GLASS_SHARDS,
+};
+
+fn set_tile(chunks: &mut [Chunk], x: i16, y: i16, tile: i16) {
+    let cx = ((x as f32 / 16.0).floor() * 16.0) as i16;
+    let cy = ((y as f32 / 16.0).floor() * 16.0) as i16;
+    if let Some(chunk) = chunks.iter_mut().find(|f| f.x == cx && f.y == cy) {
+        chunk.tiles[(x - chunk.x + (y - chunk.y) * 16) as usize] = tile;
+    }
+}
+fn get_tile<T: Borrow<Chunk>>(chunks: &[T], x: i16, y: i16) -> i16 {
     let cx = ((x as f32 / 16.0).floor() * 16.0) as i16;
     let cy = ((y as f32 / 16.0).floor() * 16.0) as i16;
-    let Some(chunk) = chunks.iter().find(|f| f.x == cx && f.y == cy) else {
+    let Some(chunk) = chunks
+        .iter()
+        .map(Borrow::borrow)
+        .find(|f: &&Chunk| f.x == cx && f.y == cy)
+    else {
         return 0;
     };
     let local_x = x - chunk.x;
@@ -233,7 +253,11 @@ fn get_tile(chunks: &[&Chunk], x: i16, y: i16) -> i16 {
 }
 type SuccessorIterator = Map<std::vec::IntoIter<(i16, i16)>, fn((i16, i16)) -> ((i16, i16), i16)>;
 
-fn generate_successors(pos: (i16, i16), chunks: &[&Chunk]) -> SuccessorIterator {
+fn generate_successors(
+    pos: (i16, i16),
+    chunks: &[&Chunk],
+    tile_entities: &HashMap<(i16, i16), TileEntity>,
+) -> SuccessorIterator {
     let mut candidates = vec![(pos.0 + 1, pos.1), (pos.0, pos.1 + 1)];
     if pos.0 > 0 {
         candidates.push((pos.0 - 1, pos.1));
@@ -241,14 +265,58 @@ fn generate_successors(pos: (i16, i16), chunks: &[&Chunk]) -> SuccessorIterator
     if pos.1 > 0 {
         candidates.push((pos.0, pos.1 - 1));
     }
-    candidates.retain(|(cx, cy)| get_tile(chunks, *cx, *cy) == 0);
+    candidates.retain(|(cx, cy)| {
+        get_tile(chunks, *cx, *cy) == 0
+            && !tile_entities
+                .get(&(*cx, *cy))
+                .is_some_and(|f| f.collision && f.enabled)
+    });
     fn map_function(p: (i16, i16)) -> ((i16, i16), i16) {
         (p, 1)
     }
     let mapped: SuccessorIterator = candidates.into_iter().map(map_function);
     mapped
 }
-#[expect(dead_code)]
+/// Steps through the tiles along a ray, returning the distance to the first tile `solid` returns
+/// true for (or `max_distance` if none was hit)
+fn walk_ray(from: Vec2, dir: Vec2, max_distance: f32, solid: impl Fn(i16, i16) -> bool) -> f32 {
+    let mut tile = (from / 16.0).floor();
+    let step = vec2(dir.x.signum(), dir.y.signum());
+    let delta = vec2((16.0 / dir.x).abs(), (16.0 / dir.y).abs());
+    let mut next = vec2(
+        if dir.x == 0.0 {
+            f32::INFINITY
+        } else if dir.x > 0.0 {
+            ((tile.x + 1.0) * 16.0 - from.x) / dir.x
+        } else {
+            (tile.x * 16.0 - from.x) / dir.x
+        },
+        if dir.y == 0.0 {
+            f32::INFINITY
+        } else if dir.y > 0.0 {
+            ((tile.y + 1.0) * 16.0 - from.y) / dir.y
+        } else {
+            (tile.y * 16.0 - from.y) / dir.y
+        },
+    );
+    loop {
+        let distance = if next.x < next.y {
+            tile.x += step.x;
+            next.x += delta.x;
+            next.x - delta.x
+        } else {
+            tile.y += step.y;
+            next.y += delta.y;
+            next.y - delta.y
+        };
+        if distance >= max_distance {
+            return max_distance;
+        }
+        if solid(tile.x as i16, tile.y as i16) {
+            return distance;
+        }
+    }
+}
 impl World {
     pub fn pathfind(&self, from: Vec2, to: Vec2) -> Option<(Vec<(i16, i16)>, i16)> {
         let to = to / 16.0;
@@ -272,12 +340,15 @@ impl World {
         let to = (to.x as i16, to.y as i16);
         pathfinding::prelude::astar(
             &(from.x as i16, from.y as i16),
-            |p| generate_successors(*p, &chunks),
+            |p| generate_successors(*p, &chunks, &self.tile_entities),
             |&(x, y)| (to.0.abs_diff(x) as i16 + to.1.abs_diff(y) as i16) / 3,
             |&p| p == to,
         )
     }
     pub fn get_interactable_spawn(&self, tile_index: i16) -> Option<Vec2> {
+        if let Some(pos) = self.markers.get(&tile_index) {
+            return Some(*pos);
+        }
         for chunk in self.interactable.iter() {
             for (i, tile) in chunk.tiles.iter().enumerate() {
                 if *tile == tile_index + 1 {
@@ -291,25 +362,135 @@ impl World {
         None
     }
     pub fn set_collision_tile(&mut self, x: i16, y: i16, tile: i16) {
-        let cx = ((x as f32 / 16.0).floor() * 16.0) as i16;
-        let cy = ((y as f32 / 16.0).floor() * 16.0) as i16;
+        set_tile(&mut self.collision, x, y, tile);
+    }
+    /// Damages a destructible tile entity or collision tile, breaking it once it runs out of health
+    pub fn damage_tile(&mut self, x: i16, y: i16, amount: f32, particles: &mut Particles) {
+        let center = vec2(x as f32, y as f32) * 16.0 + 8.0;
+        if let Some(entity) = self.tile_entities.get_mut(&(x, y))
+            && let Some(destructible) = entity.kind.destructible
+        {
+            entity.health -= amount;
+            if entity.health <= 0.0 {
+                self.tile_entities.remove(&(x, y));
+                self.broken.push((x, y));
+                particles.emit(destructible.debris, center, Vec2::ZERO, 0.0);
+            }
+            return;
+        }
+        let tile = get_tile(&self.collision, x, y) - 1;
+        if !GLASS_TILES.contains(&tile) {
+            return;
+        }
+        let health = self.tile_health.entry((x, y)).or_insert(GLASS.health);
+        *health -= amount;
+        if *health > 0.0 {
+            return;
+        }
+        self.tile_health.remove(&(x, y));
+        self.set_collision_tile(x, y, 0);
+        set_tile(&mut self.details, x, y, 0);
+        self.broken.push((x, y));
+        self.tile_entities.insert((x, y), BREACH.instantiate(0));
+        particles.emit(GLASS.debris, center, Vec2::ZERO, 0.0);
 
-        let chunk = self
-            .collision
+        let mut lights = std::mem::take(&mut self.lights);
+        for light in lights
             .iter_mut()
-            .find(|f| f.x == cx && f.y == cy)
-            .unwrap();
-        chunk.tiles[(x - chunk.x + (y - chunk.y) * 16) as usize] = tile;
+            .filter(|f| f.polygon.is_some() && f.pos.distance(center) < f.radius + 16.0)
+        {
+            light.cast_shadows(self);
+        }
+        self.lights = lights;
+    }
+    /// Centers of the hull breaches that haven't been sealed
+    pub fn open_breaches(&self) -> impl Iterator<Item = Vec2> {
+        self.tile_entities
+            .iter()
+            .filter(|(_, f)| std::ptr::eq(f.kind, &BREACH) && !f.active)
+            .map(|((x, y), _)| vec2(*x as f32, *y as f32) * 16.0 + 8.0)
+    }
+    /// Velocity that open breaches in sight drag things at a position along with, and how exposed
+    /// to the vacuum the position is from 0 to 1
+    pub fn breach_pull(&self, pos: Vec2) -> (Vec2, f32) {
+        let mut pull = Vec2::ZERO;
+        let mut exposure: f32 = 0.0;
+        for breach in self.open_breaches() {
+            let distance = breach.distance(pos);
+            if distance >= BREACH_RANGE || !self.line_of_sight(pos, breach) {
+                continue;
+            }
+            let strength = 1.0 - distance / BREACH_RANGE;
+            pull += (breach - pos).normalize_or_zero() * strength * BREACH_PULL;
+            exposure = exposure.max(strength);
+        }
+        (pull.clamp_length_max(BREACH_PULL), exposure)
+    }
+    /// Whether a position is inside an open breach, and lost to space
+    pub fn in_breach(&self, pos: Vec2) -> bool {
+        let tile = ((pos.x / 16.0).floor() as i16, (pos.y / 16.0).floor() as i16);
+        self.tile_entities
+            .get(&tile)
+            .is_some_and(|f| std::ptr::eq(f.kind, &BREACH) && !f.active)
+    }
+    /// Walks the collision layer along a ray, returning the distance to the first solid tile
+    /// (or `max_distance` if nothing was hit)
+    pub fn raycast(&self, from: Vec2, dir: Vec2, max_distance: f32) -> f32 {
+        walk_ray(from, dir, max_distance, |x, y| {
+            get_tile(&self.collision, x, y) != 0
+        })
+    }
+    /// Whether nothing solid, including closed barriers, is between two world positions
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        self.line_of_sight_with(from, to, |tile| {
+            self.tile_entities
+                .get(&tile)
+                .is_some_and(|f| f.collision && f.enabled)
+        })
+    }
+    /// Whether no collision tile and no tile that `blocked` returns true for is between two world
+    /// positions
+    pub fn line_of_sight_with(
+        &self,
+        from: Vec2,
+        to: Vec2,
+        blocked: impl Fn((i16, i16)) -> bool,
+    ) -> bool {
+        let distance = from.distance(to);
+        if distance == 0.0 {
+            return true;
+        }
+        walk_ray(from, (to - from) / distance, distance, |x, y| {
+            get_tile(&self.collision, x, y) != 0 || blocked((x, y))
+        }) >= distance
+    }
+    /// Whether a world position is inside a collision tile or a solid tile entity
+    pub fn is_solid(&self, pos: Vec2) -> bool {
+        let x = (pos.x / 16.0).floor() as i16;
+        let y = (pos.y / 16.0).floor() as i16;
+        get_tile(&self.collision, x, y) != 0
+            || self
+                .tile_entities
+                .get(&(x, y))
+                .is_some_and(|f| f.collision && f.enabled)
     }
 }
 impl Default for World {
     fn default() -> Self {
-        let xml = include_str!("../assets/station.tmx");
+        Self::new(include_str!("../assets/station.tmx"))
+    }
+}
+impl World {
+    /// Loads a world from the contents of a TMX map
+    pub fn new(xml: &str) -> Self {
         let collision = get_layer(xml, "Collision");
         let detail = get_layer(xml, "Detail");
         let interactable = get_layer(xml, "Interactable");
         let background = get_layer(xml, "Background");
         let background_details = get_layer(xml, "BackgroundDetails");
+        let ambient = get_map_property(xml, "ambient")
+            .and_then(|f| f.parse().ok())
+            .unwrap_or(1.0);
         let mut world = World {
             collision: get_all_chunks(collision),
             details: get_all_chunks(detail),
@@ -317,7 +498,24 @@ impl Default for World {
             interactable: get_all_chunks(interactable),
             background_details: get_all_chunks(background_details),
             lockers: Vec::new(),
+            pickups: Vec::new(),
+            companions: Vec::new(),
             tile_entities: HashMap::new(),
+            tile_health: HashMap::new(),
+            broken: Vec::new(),
+            lights: Vec::new(),
+            rooms: Vec::new(),
+            objects: MapObjects::default(),
+            markers: HashMap::new(),
+            patrols: get_objects(xml, "Paths")
+                .into_iter()
+                .map(|f| f.points)
+                .filter(|f| !f.is_empty())
+                .collect(),
+            ambient,
+            elite_chance: get_map_property(xml, "elite_chance")
+                .and_then(|f| f.parse().ok())
+                .unwrap_or(ELITE_CHANCE),
             x_min: 999,
             y_min: 999,
             y_max: -999,
@@ -348,19 +546,49 @@ impl Default for World {
         }
 
         let tile_entities = get_all_chunks(get_layer(xml, "TileEntities"));
+        let mut placed = Vec::new();
         for chunk in &world.interactable {
             for (index, tile) in chunk.tiles.iter().enumerate() {
-                let tile = tile - 1;
-                if tile >= 112 && tile <= 127 {
-                    let x = (index % 16) as i16 + chunk.x;
-                    let y = (index / 16) as i16 + chunk.y;
-                    world.lockers.push((
-                        vec2(x as f32 * 16.0, y as f32 * 16.0),
-                        Some(WEAPONS[tile as usize - 112]),
-                    ));
+                let x = (index % 16) as i16 + chunk.x;
+                let y = (index / 16) as i16 + chunk.y;
+                placed.push((tile - 1, vec2(x as f32 * 16.0 + 8.0, y as f32 * 16.0 + 8.0)));
+            }
+        }
+        let objects = get_objects(xml, "Entities");
+        for object in &objects {
+            if let Some(tile) = object_tile(object) {
+                placed.push((tile, vec2(object.x, object.y)));
+                if matches!(tile, 16 | 128 | 129) {
+                    world.markers.insert(tile, vec2(object.x, object.y) - 8.0);
                 }
+            } else if let Some(keycard) = object_keycard(object) {
+                world
+                    .pickups
+                    .push(Pickup::new(keycard, vec2(object.x, object.y)));
+            } else if let Some(kind) = object_companion(object) {
+                world
+                    .companions
+                    .push(Companion::new(kind, vec2(object.x, object.y) - 8.0));
             }
         }
+        for (tile, pos) in placed {
+            world.place_interactable(tile, pos);
+        }
+        for object in get_objects(xml, "Lights") {
+            let radius = object
+                .properties
+                .get("radius")
+                .and_then(|f| f.parse().ok())
+                .unwrap_or(96.0);
+            let color = object
+                .properties
+                .get("color")
+                .map(|f| parse_color(f))
+                .unwrap_or(WHITE);
+            world
+                .lights
+                .push(Light::new(vec2(object.x, object.y), radius, color));
+        }
         for chunk in &tile_entities {
             for (index, tile) in chunk.tiles.iter().enumerate() {
                 let tile = tile - 1;
@@ -369,16 +597,68 @@ impl Default for World {
                 }
                 let x = (index % 16) as i16 + chunk.x;
                 let y = (index / 16) as i16 + chunk.y;
-                if (81..=83).contains(&tile) {
-                    world
-                        .tile_entities
-                        .insert((x, y), BARRIER.instantiate(tile));
+                if let Some(kind) = kind_for_tile(tile) {
+                    world.tile_entities.insert((x, y), kind.instantiate(tile));
                 }
             }
         }
 
+        for object in get_objects(xml, "Rooms") {
+            let rect = Rect::new(object.x, object.y, object.width, object.height);
+            let waves = object.properties.get("waves").map_or("", |f| f.as_str());
+            world
+                .rooms
+                .push(Room::new(rect, waves, &world.interactable));
+        }
+        for object in &objects {
+            place_object(&mut world.tile_entities, object);
+        }
+        // wiring ids come after the object ids, so switches can't target wiring by accident
+        let next_id = objects.iter().map(|f| f.id + 1).max().unwrap_or(1);
+        place_wiring(&mut world.tile_entities, &world.interactable, next_id);
+        let breaches: Vec<(i16, i16)> = world
+            .tile_entities
+            .iter()
+            .filter(|(_, f)| std::ptr::eq(f.kind, &BREACH))
+            .map(|f| *f.0)
+            .collect();
+        for (x, y) in breaches {
+            for layer in [
+                &mut world.collision,
+                &mut world.details,
+                &mut world.background,
+                &mut world.background_details,
+            ] {
+                set_tile(layer, x, y, 0);
+            }
+        }
+        world.objects = MapObjects::load(&objects);
+
+        let mut lights = std::mem::take(&mut world.lights);
+        for light in lights.iter_mut() {
+            light.cast_shadows(&world);
+        }
+        world.lights = lights;
+
         world
     }
+    /// Places the locker, pickup or light of an "Interactable" tile, centered on a world position
+    fn place_interactable(&mut self, tile: i16, pos: Vec2) {
+        if (112..=127).contains(&tile) {
+            let item = match WEAPONS.get(tile as usize - 112) {
+                Some(weapon) => Some(LockerItem::Weapon(weapon)),
+                None => WeaponModifier::from_tile(tile).map(LockerItem::Modifier),
+            };
+            // tiles in the range without a weapon or modifier yet aren't lockers
+            if let Some(item) = item {
+                self.lockers.push((pos - 8.0, Some(item)));
+            }
+        } else if let Some(kind) = PickupKind::from_tile(tile) {
+            self.pickups.push(Pickup::new(kind, pos));
+        } else if tile == 17 {
+            self.lights.push(Light::new(pos, 96.0, WHITE));
+        }
+    }
 }
 pub struct Chunk {
     pub x: i16,
@@ -410,7 +690,20 @@ impl Chunk {
         }
     }
 }
-fn get_all_chunks(xml: &str) -> Vec<Chunk> {
+/// Draws a single tile of a layer, if there is one at that position
+pub fn draw_layer_tile(layer: &[Chunk], x: i16, y: i16, assets: &Assets) {
+    let tile = get_tile(layer, x, y) - 1;
+    if tile >= 0 {
+        assets.tileset.draw_tile(
+            x as f32 * 16.0,
+            y as f32 * 16.0,
+            (tile % 16) as f32,
+            (tile / 16) as f32,
+            None,
+        );
+    }
+}
+pub fn get_all_chunks(xml: &str) -> Vec<Chunk> {
     let mut chunks = Vec::new();
     let mut xml = xml.to_string();
     while let Some((current, remains)) = xml.split_once("</chunk>") {
@@ -458,7 +751,108 @@ fn parse_chunk(xml: &str) -> Chunk {
     Chunk { x, y, tiles: chunk }
 }
 
-fn get_layer<'a>(xml: &'a str, layer: &str) -> &'a str {
+pub struct TmxObject {
+    pub id: u32,
+    /// The object's type, called class since Tiled 1.9
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub properties: HashMap<String, String>,
+    /// Points of a polyline object, in world space
+    pub points: Vec<Vec2>,
+}
+fn get_attribute<'a>(tag: &'a str, attribute: &str) -> Option<&'a str> {
+    let split = format!(" {attribute}=\"");
+    Some(tag.split_once(&split)?.1.split_once("\"")?.0)
+}
+fn get_properties(xml: &str) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    let Some((_, xml)) = xml.split_once("<properties>") else {
+        return properties;
+    };
+    let xml = xml.split_once("</properties>").unwrap().0;
+    for property in xml.split("<property").skip(1) {
+        if let Some(name) = get_attribute(property, "name")
+            && let Some(value) = get_attribute(property, "value")
+        {
+            properties.insert(name.to_string(), value.to_string());
+        }
+    }
+    properties
+}
+/// Reads all objects in an object layer, or nothing if the layer doesn't exist
+pub fn get_objects(xml: &str, layer: &str) -> Vec<TmxObject> {
+    let split = format!(" name=\"{layer}\"");
+    let Some((_, group)) = xml
+        .split("<objectgroup")
+        .map(|f| f.split_once("</objectgroup>").map(|f| f.0).unwrap_or(f))
+        .filter_map(|f| f.split_once('>'))
+        .find(|(tag, _)| tag.contains(&split))
+    else {
+        return Vec::new();
+    };
+    let mut objects = Vec::new();
+    for object in group.split("<object").skip(1) {
+        let tag = object.split_once('>').unwrap().0;
+        let x = get_attribute(tag, "x").unwrap().parse().unwrap();
+        let y = get_attribute(tag, "y").unwrap().parse().unwrap();
+        let body = if tag.ends_with('/') {
+            ""
+        } else {
+            object.split_once("</object>").unwrap().0
+        };
+        let points = body
+            .split_once("<polyline")
+            .and_then(|f| get_attribute(f.1, "points"))
+            .map(|points| {
+                points
+                    .split(' ')
+                    .map(|point| {
+                        let (px, py) = point.split_once(',').unwrap();
+                        vec2(
+                            x + px.parse::<f32>().unwrap(),
+                            y + py.parse::<f32>().unwrap(),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        objects.push(TmxObject {
+            id: get_attribute(tag, "id").map_or(0, |f| f.parse().unwrap()),
+            class: get_attribute(tag, "type")
+                .or_else(|| get_attribute(tag, "class"))
+                .unwrap_or_default()
+                .to_string(),
+            x,
+            y,
+            width: get_attribute(tag, "width").map_or(0.0, |f| f.parse().unwrap()),
+            height: get_attribute(tag, "height").map_or(0.0, |f| f.parse().unwrap()),
+            properties: get_properties(body),
+            points,
+        });
+    }
+    objects
+}
+/// Reads a custom property of the map itself
+pub fn get_map_property<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let header = xml.split_once("<tileset").unwrap().0;
+    let header = header.split_once("<properties>")?.1;
+    let split = format!(" name=\"{name}\"");
+    get_attribute(header.split_once(&split)?.1, "value")
+}
+/// Parses a Tiled colour in the form `#AARRGGBB` or `#RRGGBB`
+fn parse_color(value: &str) -> Color {
+    let hex = u32::from_str_radix(value.trim_start_matches('#'), 16).unwrap();
+    if value.len() > 7 {
+        Color::from_hex(hex & 0xffffff).with_alpha((hex >> 24) as f32 / 255.0)
+    } else {
+        Color::from_hex(hex)
+    }
+}
+
+pub fn get_layer<'a>(xml: &'a str, layer: &str) -> &'a str {
     let split = format!(" name=\"{layer}");
     xml.split_once(&split)
         .unwrap()
@@ -0,0 +1,227 @@
+use std::sync::LazyLock;
+
+use crate::{
+    enemy::{
+        EnemyMovement, EnemyState, EnemyType, ProjectileFiring, StateChangeCondition, StateTarget,
+    },
+    modifiers::WeaponModifier,
+    pickups::PickupKind,
+    player::ALIEN_BALL,
+    status::{Damage, DamageType},
+};
+
+/// How long the camera spends looking at a boss when it first appears
+pub const BOSS_INTRO_TIME: f32 = 2.4;
+
+pub struct BossPhase {
+    /// Fraction of the boss' health at which this phase starts
+    pub health: f32,
+    pub ty: EnemyType,
+}
+pub struct BossType {
+    pub name: &'static str,
+    pub health: f32,
+    pub phases: Vec<BossPhase>,
+}
+/// Boss specific state of an enemy
+pub struct Boss {
+    pub ty: &'static BossType,
+    pub phase: usize,
+    /// Id of the wiring that spawned the boss, whose barriers open again once it dies
+    pub arena: Option<u32>,
+}
+
+/// Bosses are spawned by tiles in the "Interactable" layer starting at tile 135
+pub static BOSSES: LazyLock<Vec<BossType>> = LazyLock::new(|| {
+    let overseer_health = 600.0;
+    let overseer = BossType {
+        name: "THE OVERSEER",
+        health: overseer_health,
+        phases: vec![
+            BossPhase {
+                health: 1.0,
+                ty: EnemyType {
+                    name: "overseer",
+                    health: overseer_health,
+                    cost: 0,
+                    states: vec![
+                        EnemyState {
+                            name: "taunt",
+                            animation_id: 6,
+                            speed: 0.0,
+                            movement: EnemyMovement::None,
+                            projectile_firing: ProjectileFiring::None,
+                            transitions: vec![(
+                                StateChangeCondition::AnimationFinish,
+                                StateTarget::Next,
+                            )],
+                            damage_on_exit: None,
+                            pattern: None,
+                        },
+                        EnemyState {
+                            name: "burst",
+                            animation_id: 4,
+                            speed: 0.0,
+                            movement: EnemyMovement::Chase,
+                            projectile_firing: ProjectileFiring::None,
+                            transitions: vec![(
+                                StateChangeCondition::PatternFinish,
+                                StateTarget::Next,
+                            )],
+                            damage_on_exit: None,
+                            pattern: Some("aimed_burst"),
+                        },
+                        EnemyState {
+                            name: "spiral",
+                            animation_id: 5,
+                            speed: 30.0,
+                            movement: EnemyMovement::Chase,
+                            projectile_firing: ProjectileFiring::None,
+                            transitions: vec![(
+                                StateChangeCondition::PatternFinish,
+                                StateTarget::Next,
+                            )],
+                            damage_on_exit: None,
+                            pattern: Some("spiral"),
+                        },
+                    ],
+                    loot: Vec::new(),
+                    death_hazard: None,
+                    resistances: Vec::new(),
+                },
+            },
+            BossPhase {
+                health: 0.6,
+                ty: EnemyType {
+                    name: "overseer",
+                    health: overseer_health,
+                    cost: 0,
+                    states: vec![
+                        EnemyState {
+                            name: "rings",
+                            animation_id: 5,
+                            speed: 50.0,
+                            movement: EnemyMovement::Orbit(80.0),
+                            projectile_firing: ProjectileFiring::None,
+                            transitions: vec![(
+                                StateChangeCondition::PatternFinish,
+                                StateTarget::Next,
+                            )],
+                            damage_on_exit: None,
+                            pattern: Some("rotating_rings"),
+                        },
+                        EnemyState {
+                            name: "dash",
+                            animation_id: 5,
+                            speed: 180.0,
+                            movement: EnemyMovement::Straight,
+                            projectile_firing: ProjectileFiring::None,
+                            transitions: vec![(StateChangeCondition::HitWall, StateTarget::Next)],
+                            damage_on_exit: None,
+                            pattern: None,
+                        },
+                        EnemyState {
+                            name: "slam",
+                            animation_id: 6,
+                            speed: 0.0,
+                            movement: EnemyMovement::Chase,
+                            projectile_firing: ProjectileFiring::Around(&ALIEN_BALL, 16),
+                            transitions: vec![(
+                                StateChangeCondition::AnimationFinish,
+                                StateTarget::Random(vec![("spiral", 2.0), ("dash", 1.0)]),
+                            )],
+                            damage_on_exit: Some(Damage::new(30.0, DamageType::Melee)),
+                            pattern: None,
+                        },
+                        EnemyState {
+                            name: "spiral",
+                            animation_id: 4,
+                            speed: 20.0,
+                            movement: EnemyMovement::Chase,
+                            projectile_firing: ProjectileFiring::None,
+                            transitions: vec![(
+                                StateChangeCondition::PatternFinish,
+                                StateTarget::Next,
+                            )],
+                            damage_on_exit: None,
+                            pattern: Some("double_spiral"),
+                        },
+                    ],
+                    loot: Vec::new(),
+                    death_hazard: None,
+                    resistances: Vec::new(),
+                },
+            },
+            BossPhase {
+                health: 0.25,
+                ty: EnemyType {
+                    name: "overseer",
+                    health: overseer_health,
+                    cost: 0,
+                    states: vec![
+                        EnemyState {
+                            name: "bombs",
+                            animation_id: 4,
+                            speed: 0.0,
+                            movement: EnemyMovement::Chase,
+                            projectile_firing: ProjectileFiring::None,
+                            transitions: vec![(
+                                StateChangeCondition::PatternFinish,
+                                StateTarget::Next,
+                            )],
+                            damage_on_exit: None,
+                            pattern: Some("bombs"),
+                        },
+                        EnemyState {
+                            name: "dash",
+                            animation_id: 5,
+                            speed: 220.0,
+                            movement: EnemyMovement::Straight,
+                            projectile_firing: ProjectileFiring::None,
+                            transitions: vec![(StateChangeCondition::HitWall, StateTarget::Next)],
+                            damage_on_exit: None,
+                            pattern: Some("overseer_trail"),
+                        },
+                        EnemyState {
+                            name: "slam",
+                            animation_id: 6,
+                            speed: 0.0,
+                            movement: EnemyMovement::Chase,
+                            projectile_firing: ProjectileFiring::Around(&ALIEN_BALL, 20),
+                            transitions: vec![(
+                                StateChangeCondition::PatternFinish,
+                                StateTarget::Next,
+                            )],
+                            damage_on_exit: Some(Damage::new(30.0, DamageType::Melee)),
+                            pattern: Some("seekers"),
+                        },
+                        EnemyState {
+                            name: "waves",
+                            animation_id: 5,
+                            speed: 40.0,
+                            movement: EnemyMovement::Chase,
+                            projectile_firing: ProjectileFiring::None,
+                            transitions: vec![(
+                                StateChangeCondition::PatternFinish,
+                                StateTarget::Next,
+                            )],
+                            damage_on_exit: None,
+                            pattern: Some("waves"),
+                        },
+                    ],
+                    loot: vec![
+                        (PickupKind::Health, 1.0),
+                        (PickupKind::Modifier(WeaponModifier::ExtraProjectile), 1.0),
+                    ],
+                    death_hazard: None,
+                    resistances: Vec::new(),
+                },
+            },
+        ],
+    };
+    let bosses = vec![overseer];
+    for phase in bosses.iter().flat_map(|f| &f.phases) {
+        phase.ty.check_transitions();
+    }
+    bosses
+});
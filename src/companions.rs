@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::{
+    assets::{Assets, World},
+    enemy::{Enemy, HEALTHBAR_COLOR},
+    particles::{MUZZLE_FLASH, Particles},
+    pickups::PickupKind,
+    player::{ENERGY_SHOT, Player, Projectile, update_physicsbody},
+    status::{Damage, Statuses},
+    utils::multiply,
+};
+
+/// Distance the player has to come within, in sight, for a companion to start following them
+const FIND_RANGE: f32 = 64.0;
+const DRONE_ORBIT_RADIUS: f32 = 20.0;
+/// Radians per second the drone circles the player at
+const DRONE_ORBIT_SPEED: f32 = 2.5;
+/// Distance from its place in the orbit past which the drone warps back, after getting stuck
+const DRONE_LEASH: f32 = 160.0;
+const DRONE_RANGE: f32 = 112.0;
+const DRONE_FIRE_DELAY: f32 = 0.4;
+const SURVIVOR_SPEED: f32 = 55.0;
+/// Distance survivors stop at, to not crowd the player
+const SURVIVOR_KEEP_DISTANCE: f32 = 24.0;
+/// Distance from the escape pod door survivors have to be within to board it
+pub const SURVIVOR_BOARD_RANGE: f32 = 64.0;
+/// Supplies each survivor leaves at the escape pod door for the player when they board
+pub const SURVIVOR_REWARD: PickupKind = PickupKind::Shield;
+const DRONE_COLOR: Color = Color::from_hex(0x8ab4c8);
+const DRONE_LIGHT_COLOR: Color = Color::from_hex(0x60f0a0);
+const SURVIVOR_COLOR: Color = Color::from_hex(0xf0b070);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CompanionKind {
+    /// Orbits the player and shoots the nearest enemy
+    Drone,
+    /// Stranded crew member who follows the player, and has to make it to the escape pod
+    Survivor,
+}
+impl CompanionKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "drone" => Some(Self::Drone),
+            "survivor" => Some(Self::Survivor),
+            _ => None,
+        }
+    }
+    fn health(&self) -> f32 {
+        match self {
+            CompanionKind::Drone => 30.0,
+            CompanionKind::Survivor => 40.0,
+        }
+    }
+}
+
+/// Friendly character which moves like the player and is hit by enemy projectiles like them
+pub struct Companion {
+    pub kind: CompanionKind,
+    /// Top left corner, like the player's
+    pub pos: Vec2,
+    pub velocity: Vec2,
+    pub health: f32,
+    /// Whether the player has found the companion, making it follow them and enemies go for it
+    pub following: bool,
+    pub statuses: Statuses,
+    animation_time: f32,
+    attack_counter: f32,
+    path: Option<VecDeque<(i16, i16)>>,
+    time_til_pathfind: f32,
+}
+impl Companion {
+    pub fn new(kind: CompanionKind, pos: Vec2) -> Self {
+        Self {
+            kind,
+            pos,
+            velocity: Vec2::ZERO,
+            health: kind.health(),
+            following: false,
+            statuses: Statuses::default(),
+            animation_time: 0.0,
+            attack_counter: 0.0,
+            path: None,
+            time_til_pathfind: 0.0,
+        }
+    }
+    /// Deals typed damage and inflicts its status effect
+    pub fn hit(&mut self, damage: Damage) {
+        self.health -= damage.amount;
+        if let Some(effect) = damage.effect {
+            self.statuses.apply(effect);
+        }
+    }
+    pub fn update(
+        &mut self,
+        delta_time: f32,
+        player: &Player,
+        enemies: &[Enemy],
+        world: &World,
+        projectiles: &mut Vec<Projectile>,
+        particles: &mut Particles,
+    ) {
+        self.animation_time += delta_time;
+        for (amount, _) in self.statuses.update(delta_time) {
+            self.health -= amount;
+        }
+        let center = self.pos + 8.0;
+        let player_center = player.pos + 8.0;
+        if !self.following {
+            self.following = center.distance(player_center) < FIND_RANGE
+                && world.line_of_sight(center, player_center);
+            return;
+        }
+        let speed = self.statuses.speed();
+        match self.kind {
+            CompanionKind::Drone => {
+                let orbit = player.pos
+                    + Vec2::from_angle(self.animation_time * DRONE_ORBIT_SPEED)
+                        * DRONE_ORBIT_RADIUS;
+                if self.pos.distance(orbit) > DRONE_LEASH {
+                    self.pos = orbit;
+                }
+                self.velocity = (orbit - self.pos) * 6.0 * speed;
+                self.pos = update_physicsbody(self.pos, &mut self.velocity, delta_time, world);
+
+                self.attack_counter -= delta_time;
+                if self.attack_counter <= 0.0
+                    && speed > 0.0
+                    && let Some(target) = enemies
+                        .iter()
+                        .filter(|f| !f.emerging)
+                        .map(|f| f.pos)
+                        .filter(|f| {
+                            f.distance(center) < DRONE_RANGE && world.line_of_sight(center, *f)
+                        })
+                        .min_by(|a, b| a.distance(center).total_cmp(&b.distance(center)))
+                {
+                    let dir = (target - center).normalize_or(Vec2::X);
+                    projectiles.push(Projectile::new(&ENERGY_SHOT, center, dir, true));
+                    particles.emit(&MUZZLE_FLASH, center + dir * 4.0, dir, delta_time);
+                    self.attack_counter = DRONE_FIRE_DELAY;
+                }
+            }
+            CompanionKind::Survivor => {
+                if center.distance(player_center) < SURVIVOR_KEEP_DISTANCE {
+                    self.velocity = Vec2::ZERO;
+                    self.path = None;
+                } else {
+                    self.time_til_pathfind -= delta_time;
+                    if self.path.is_none() || self.time_til_pathfind <= 0.0 {
+                        self.time_til_pathfind = 1.0;
+                        self.path = world.pathfind(center, player_center).map(|f| f.0.into());
+                    }
+                    let mut target = player.pos;
+                    if let Some(path) = &mut self.path
+                        && let Some((x, y)) = path.get(1)
+                    {
+                        let next = vec2(*x as f32 * 16.0, *y as f32 * 16.0);
+                        if next.distance(self.pos) < 4.0 {
+                            path.pop_front();
+                        }
+                        target = next;
+                    }
+                    self.velocity =
+                        (target - self.pos).normalize_or_zero() * SURVIVOR_SPEED * speed;
+                }
+                self.pos = update_physicsbody(self.pos, &mut self.velocity, delta_time, world);
+            }
+        }
+        let (mut pull, _) = world.breach_pull(center);
+        if pull != Vec2::ZERO {
+            self.pos = update_physicsbody(self.pos, &mut pull, delta_time, world);
+        }
+    }
+    pub fn draw(&self, assets: &Assets) {
+        let pos = self.pos.floor();
+        let tint = self.statuses.tint();
+        match self.kind {
+            CompanionKind::Drone => {
+                let bob = (self.animation_time * 6.0).sin().round();
+                let center = pos + vec2(8.0, 8.0 + bob);
+                draw_rectangle(
+                    center.x - 4.0,
+                    center.y - 2.0,
+                    8.0,
+                    4.0,
+                    multiply(DRONE_COLOR, tint),
+                );
+                draw_rectangle(
+                    center.x - 2.0,
+                    center.y - 3.0,
+                    4.0,
+                    6.0,
+                    multiply(DRONE_COLOR, tint),
+                );
+                draw_rectangle(center.x - 6.0, center.y - 3.0, 3.0, 1.0, GRAY);
+                draw_rectangle(center.x + 3.0, center.y - 3.0, 3.0, 1.0, GRAY);
+                if self.following && self.animation_time % 1.0 < 0.5 {
+                    draw_rectangle(center.x - 1.0, center.y - 1.0, 2.0, 2.0, DRONE_LIGHT_COLOR);
+                }
+            }
+            CompanionKind::Survivor => {
+                draw_texture_ex(
+                    assets.player.animations[if self.velocity == Vec2::ZERO { 0 } else { 1 }]
+                        .get_at_time((self.animation_time * 1000.0) as u32),
+                    pos.x,
+                    pos.y,
+                    multiply(SURVIVOR_COLOR, tint),
+                    DrawTextureParams {
+                        flip_x: self.velocity.x < 0.0,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        if self.health < self.kind.health() {
+            let width = 14.0;
+            draw_rectangle(pos.x, pos.y - 5.0, width + 2.0, 4.0, BLACK);
+            draw_rectangle(
+                pos.x + 1.0,
+                pos.y - 4.0,
+                self.health.max(0.0) / self.kind.health() * width,
+                2.0,
+                HEALTHBAR_COLOR,
+            );
+        }
+    }
+}
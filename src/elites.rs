@@ -0,0 +1,86 @@
+use std::f32::consts::PI;
+
+use macroquad::prelude::*;
+
+/// Chance of an enemy spawning as an elite, on maps without an `elite_chance` property
+pub const ELITE_CHANCE: f32 = 0.08;
+
+/// Modifier rolled onto an enemy as it spawns, making it tougher and changing how it looks
+#[derive(Clone, Copy, PartialEq)]
+pub enum EliteModifier {
+    Armored,
+    Fast,
+    /// Splits into two weaker copies on death
+    Splitting,
+    /// Leaves acid where it dies, and is immune to it
+    Acidic,
+    /// Has a shield on top of its health
+    Shielded,
+    /// Bursts into projectiles on death
+    Volatile,
+}
+impl EliteModifier {
+    pub const ALL: [Self; 6] = [
+        Self::Armored,
+        Self::Fast,
+        Self::Splitting,
+        Self::Acidic,
+        Self::Shielded,
+        Self::Volatile,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            EliteModifier::Armored => "Armored",
+            EliteModifier::Fast => "Fast",
+            EliteModifier::Splitting => "Splitting",
+            EliteModifier::Acidic => "Acidic",
+            EliteModifier::Shielded => "Shielded",
+            EliteModifier::Volatile => "Volatile",
+        }
+    }
+    /// Multiplier of the enemy's health
+    pub fn health(&self) -> f32 {
+        match self {
+            EliteModifier::Armored => 2.5,
+            EliteModifier::Fast => 1.2,
+            _ => 1.5,
+        }
+    }
+    /// Multiplier of the damage the enemy deals
+    pub fn damage(&self) -> f32 {
+        match self {
+            EliteModifier::Acidic | EliteModifier::Volatile => 1.5,
+            _ => 1.25,
+        }
+    }
+    /// Multiplier of the enemy's movement speed
+    pub fn speed(&self) -> f32 {
+        match self {
+            EliteModifier::Armored => 0.8,
+            EliteModifier::Fast => 1.6,
+            _ => 1.0,
+        }
+    }
+    /// How far around the colour wheel the palette of the enemy's sprite is turned, in radians
+    pub fn hue(&self) -> f32 {
+        match self {
+            EliteModifier::Armored => PI,
+            EliteModifier::Fast => -1.2,
+            EliteModifier::Splitting => 2.2,
+            EliteModifier::Acidic => 0.6,
+            EliteModifier::Shielded => 1.5,
+            EliteModifier::Volatile => -2.2,
+        }
+    }
+    /// Colour of the name tag, also used to tint the sprite when the palette swap is unavailable
+    pub fn color(&self) -> Color {
+        match self {
+            EliteModifier::Armored => Color::from_hex(0xb0b0c0),
+            EliteModifier::Fast => Color::from_hex(0xf0e060),
+            EliteModifier::Splitting => Color::from_hex(0xe060d0),
+            EliteModifier::Acidic => Color::from_hex(0x9ae060),
+            EliteModifier::Shielded => Color::from_hex(0x60a0f0),
+            EliteModifier::Volatile => Color::from_hex(0xf06030),
+        }
+    }
+}
@@ -1,129 +1,599 @@
-use std::{collections::VecDeque, f32::consts::PI, sync::LazyLock};
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    sync::{
+        LazyLock,
+        atomic::{AtomicU32, Ordering},
+    },
+};
 
 use crate::{
     assets::{Assets, World},
-    player::{ALIEN_BALL, Player, Projectile, ProjectileType, update_physicsbody},
+    boss::{Boss, BossType},
+    companions::Companion,
+    elites::EliteModifier,
+    modifiers::WeaponModifier,
+    particles::{EMERGE_DEBRIS, EXPLOSION, Particles, TELEPORT_SPARKS},
+    patterns::PatternRunner,
+    pickups::{Pickup, PickupKind, SHIELD_COLOR},
+    player::{ALIEN_BALL, FROST_BALL, Player, Projectile, ProjectileType, update_physicsbody},
+    settings::settings,
+    status::{Damage, DamageType, StatusKind, Statuses},
+    tile_entities::{ACID, TileEntityKind},
+    utils::multiply,
 };
 use macroquad::prelude::*;
 
 pub struct EnemyType {
+    /// Name used to refer to the enemy from the map
+    pub name: &'static str,
     pub health: f32,
+    /// How much of a wave's difficulty budget the enemy uses up in arena mode
+    pub cost: u32,
     pub states: Vec<EnemyState>,
+    /// Pickups that can be dropped on death, along with their chance of dropping
+    pub loot: Vec<(PickupKind, f32)>,
+    /// Hazard left on the floor where the enemy dies
+    pub death_hazard: Option<&'static TileEntityKind>,
+    /// Multipliers of the damage taken of each type, where 0 is immune. Types not listed deal
+    /// full damage.
+    pub resistances: Vec<(DamageType, f32)>,
+}
+impl EnemyType {
+    pub fn resistance(&self, ty: DamageType) -> f32 {
+        self.resistances
+            .iter()
+            .find(|f| f.0 == ty)
+            .map_or(1.0, |f| f.1)
+    }
+    /// Panics if a transition leads to a state the enemy doesn't have, so a typo shows up as soon
+    /// as the tables are built instead of when the transition first happens
+    pub fn check_transitions(&self) {
+        for (_, target) in self.states.iter().flat_map(|f| &f.transitions) {
+            let names = match target {
+                StateTarget::Next => Vec::new(),
+                StateTarget::Named(name) => vec![*name],
+                StateTarget::Random(choices) => choices.iter().map(|f| f.0).collect(),
+            };
+            for name in names {
+                assert!(
+                    self.states.iter().any(|f| f.name == name),
+                    "{}: no enemy state named {name}",
+                    self.name
+                );
+            }
+        }
+    }
 }
 pub enum ProjectileFiring {
     None,
     Forwards(&'static ProjectileType),
+    /// Fires at where the player will be by the time the projectile reaches them
+    Aimed {
+        projectile: &'static ProjectileType,
+        /// How much of the player's movement to account for, from 0 (none) to 1 (all of it)
+        lead: f32,
+        /// Largest random error in the aim, in radians
+        spread: f32,
+    },
     Around(&'static ProjectileType, u8),
 }
 pub enum StateChangeCondition {
+    #[expect(dead_code)]
     Never,
     Always,
     HitWall,
     NearPlayer,
     AnimationFinish,
+    /// The state's bullet pattern has finished
+    PatternFinish,
+    /// Seconds spent in the state
+    Timer(f32),
+    /// Fraction of the enemy's max health
+    HealthBelow(f32),
+    LineOfSight,
+    /// Distance to the player is between the minimum and maximum
+    PlayerDistance(f32, f32),
+    /// The enemy was hit since entering the state
+    TookDamage,
+    /// A burrow or teleport has finished and the enemy is back in sight
+    MovementFinish,
+    All(Vec<StateChangeCondition>),
+}
+/// What an enemy knows about where the player is
+pub enum Awareness {
+    /// Hasn't noticed the player, and stays put
+    Idle,
+    /// Can see the player
+    Alert,
+    /// Lost sight of the player or heard something, and heads to where it was for a while
+    Search(f32),
+}
+/// State to go to once a transition's condition is met
+pub enum StateTarget {
+    Next,
+    Named(&'static str),
+    /// Picks one of the named states, with chances weighted by the given amounts
+    Random(Vec<(&'static str, f32)>),
 }
 pub enum EnemyMovement {
     Chase,
     None,
     Pathfind,
     Straight,
+    /// Circles the player sideways while keeping this distance from them
+    Strafe(f32),
+    Flee,
+    /// Circles the player at this distance, facing where it's going
+    Orbit(f32),
+    /// Walks in random directions, even before noticing the player
+    Wander,
+    /// Walks along the nearest path from the map's "Paths" layer, even before noticing the player
+    Patrol,
+    /// Sinks into the floor and resurfaces near the player
+    Burrow,
+    /// Fades out and reappears near the player
+    Teleport,
 }
 pub struct EnemyState {
+    pub name: &'static str,
     pub animation_id: usize,
     pub speed: f32,
     pub movement: EnemyMovement,
     pub projectile_firing: ProjectileFiring,
-    pub change_state: StateChangeCondition,
-    pub damage_on_exit: Option<f32>,
+    /// Checked in order, the first one whose condition is met is taken
+    pub transitions: Vec<(StateChangeCondition, StateTarget)>,
+    /// Damage dealt to the player, if they're close, when leaving the state
+    pub damage_on_exit: Option<Damage>,
+    /// Bullet pattern from `assets/patterns.txt` to play while in this state
+    pub pattern: Option<&'static str>,
 }
 
 pub static ENEMIES: LazyLock<Vec<EnemyType>> = LazyLock::new(|| {
     let greeno: EnemyType = EnemyType {
+        name: "greeno",
         states: vec![
             EnemyState {
+                name: "chase",
                 animation_id: 0,
                 speed: 25.0,
                 movement: EnemyMovement::Chase,
                 projectile_firing: ProjectileFiring::None,
-                change_state: StateChangeCondition::NearPlayer,
+                transitions: vec![(StateChangeCondition::NearPlayer, StateTarget::Next)],
                 damage_on_exit: None,
+                pattern: None,
             },
             EnemyState {
+                name: "attack",
                 animation_id: 1,
                 speed: 0.0,
                 movement: EnemyMovement::Chase,
                 projectile_firing: ProjectileFiring::None,
-                change_state: StateChangeCondition::AnimationFinish,
-                damage_on_exit: Some(15.0),
+                transitions: vec![(StateChangeCondition::AnimationFinish, StateTarget::Next)],
+                damage_on_exit: Some(Damage::new(15.0, DamageType::Melee)),
+                pattern: None,
             },
         ],
         health: 20.0,
+        cost: 2,
+        loot: vec![(PickupKind::Health, 0.1), (PickupKind::Ammo, 0.15)],
+        death_hazard: Some(&ACID),
+        resistances: Vec::new(),
     };
     let dog: EnemyType = EnemyType {
+        name: "dog",
         states: vec![
             EnemyState {
+                name: "chase",
                 animation_id: 2,
                 speed: 80.0,
                 movement: EnemyMovement::Chase,
                 projectile_firing: ProjectileFiring::None,
-                change_state: StateChangeCondition::NearPlayer,
+                transitions: vec![(StateChangeCondition::NearPlayer, StateTarget::Next)],
                 damage_on_exit: None,
+                pattern: None,
             },
             EnemyState {
+                name: "attack",
                 animation_id: 3,
                 speed: 0.0,
                 movement: EnemyMovement::Chase,
                 projectile_firing: ProjectileFiring::None,
-                change_state: StateChangeCondition::AnimationFinish,
-                damage_on_exit: Some(5.0),
+                transitions: vec![(StateChangeCondition::AnimationFinish, StateTarget::Next)],
+                damage_on_exit: Some(Damage::new(5.0, DamageType::Melee)),
+                pattern: None,
             },
         ],
         health: 9.0,
+        cost: 1,
+        loot: vec![(PickupKind::Ammo, 0.1)],
+        death_hazard: None,
+        resistances: Vec::new(),
     };
     let shooter: EnemyType = EnemyType {
+        name: "shooter",
         states: vec![EnemyState {
+            name: "shoot",
             animation_id: 4,
-            speed: 0.0,
-            movement: EnemyMovement::Chase,
-            projectile_firing: ProjectileFiring::Forwards(&ALIEN_BALL),
-            change_state: StateChangeCondition::AnimationFinish,
+            speed: 45.0,
+            movement: EnemyMovement::Strafe(96.0),
+            projectile_firing: ProjectileFiring::Aimed {
+                projectile: &ALIEN_BALL,
+                lead: 0.8,
+                spread: 0.1,
+            },
+            transitions: vec![(StateChangeCondition::AnimationFinish, StateTarget::Next)],
             damage_on_exit: None,
+            pattern: None,
         }],
         health: 9.0,
+        cost: 2,
+        loot: vec![
+            (PickupKind::Ammo, 0.2),
+            (PickupKind::FireRateBoost, 0.05),
+            (PickupKind::Modifier(WeaponModifier::Focused), 0.03),
+        ],
+        death_hazard: None,
+        resistances: Vec::new(),
     };
     let bigo: EnemyType = EnemyType {
+        name: "bigo",
         states: vec![
             EnemyState {
+                name: "windup",
                 animation_id: 5,
                 speed: 0.0,
                 movement: EnemyMovement::Chase,
                 projectile_firing: ProjectileFiring::None,
-                change_state: StateChangeCondition::Always,
+                transitions: vec![(StateChangeCondition::Always, StateTarget::Next)],
                 damage_on_exit: None,
+                pattern: None,
             },
             EnemyState {
+                name: "charge",
                 animation_id: 5,
                 speed: 160.0,
                 movement: EnemyMovement::Straight,
                 projectile_firing: ProjectileFiring::None,
-                change_state: StateChangeCondition::HitWall,
+                transitions: vec![(StateChangeCondition::HitWall, StateTarget::Next)],
                 damage_on_exit: None,
+                pattern: None,
             },
             EnemyState {
+                name: "slam",
                 animation_id: 6,
                 speed: 0.0,
                 movement: EnemyMovement::Chase,
                 projectile_firing: ProjectileFiring::Around(&ALIEN_BALL, 10),
-                change_state: StateChangeCondition::AnimationFinish,
-                damage_on_exit: Some(30.0),
+                transitions: vec![(StateChangeCondition::AnimationFinish, StateTarget::Next)],
+                damage_on_exit: Some(Damage::new(30.0, DamageType::Melee)),
+                pattern: None,
             },
         ],
         health: 90.0,
+        cost: 5,
+        loot: vec![
+            (PickupKind::Health, 0.5),
+            (PickupKind::DamageBoost, 0.25),
+            (PickupKind::Shield, 0.25),
+            (PickupKind::Modifier(WeaponModifier::Explosive), 0.15),
+            (PickupKind::Modifier(WeaponModifier::Piercing), 0.15),
+        ],
+        death_hazard: None,
+        resistances: Vec::new(),
+    };
+    // bigo that sizes the player up before charging, and enrages when low on health
+    let brute: EnemyType = EnemyType {
+        name: "brute",
+        states: vec![
+            EnemyState {
+                name: "windup",
+                animation_id: 5,
+                speed: 0.0,
+                movement: EnemyMovement::Chase,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![
+                    (
+                        StateChangeCondition::HealthBelow(0.35),
+                        StateTarget::Named("enrage"),
+                    ),
+                    (
+                        StateChangeCondition::All(vec![
+                            StateChangeCondition::LineOfSight,
+                            StateChangeCondition::PlayerDistance(0.0, 200.0),
+                        ]),
+                        StateTarget::Named("charge"),
+                    ),
+                    (
+                        StateChangeCondition::TookDamage,
+                        StateTarget::Named("charge"),
+                    ),
+                    (
+                        StateChangeCondition::Timer(2.0),
+                        StateTarget::Named("charge"),
+                    ),
+                ],
+                damage_on_exit: None,
+                pattern: None,
+            },
+            EnemyState {
+                name: "charge",
+                animation_id: 5,
+                speed: 160.0,
+                movement: EnemyMovement::Straight,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![(StateChangeCondition::HitWall, StateTarget::Next)],
+                damage_on_exit: None,
+                pattern: None,
+            },
+            EnemyState {
+                name: "slam",
+                animation_id: 6,
+                speed: 0.0,
+                movement: EnemyMovement::Chase,
+                projectile_firing: ProjectileFiring::Around(&ALIEN_BALL, 10),
+                transitions: vec![(
+                    StateChangeCondition::AnimationFinish,
+                    StateTarget::Random(vec![("windup", 3.0), ("slam", 1.0)]),
+                )],
+                damage_on_exit: Some(
+                    Damage::new(30.0, DamageType::Melee).with(StatusKind::Stun, 0.5),
+                ),
+                pattern: None,
+            },
+            EnemyState {
+                name: "enrage",
+                animation_id: 6,
+                speed: 0.0,
+                movement: EnemyMovement::Chase,
+                projectile_firing: ProjectileFiring::Around(&ALIEN_BALL, 16),
+                transitions: vec![(
+                    StateChangeCondition::AnimationFinish,
+                    StateTarget::Named("charge"),
+                )],
+                damage_on_exit: None,
+                pattern: None,
+            },
+        ],
+        health: 90.0,
+        cost: 6,
+        loot: vec![
+            (PickupKind::Health, 0.5),
+            (PickupKind::DamageBoost, 0.25),
+            (PickupKind::Shield, 0.25),
+            (PickupKind::Modifier(WeaponModifier::Explosive), 0.15),
+            (PickupKind::Modifier(WeaponModifier::Piercing), 0.15),
+        ],
+        death_hazard: None,
+        resistances: vec![(DamageType::Explosive, 0.5), (DamageType::Melee, 0.5)],
+    };
+    // greeno that roams until it sees the player, and burrows up to them when they keep away
+    let lurker: EnemyType = EnemyType {
+        name: "lurker",
+        states: vec![
+            EnemyState {
+                name: "wander",
+                animation_id: 0,
+                speed: 15.0,
+                movement: EnemyMovement::Wander,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![(
+                    StateChangeCondition::LineOfSight,
+                    StateTarget::Named("chase"),
+                )],
+                damage_on_exit: None,
+                pattern: None,
+            },
+            EnemyState {
+                name: "chase",
+                animation_id: 0,
+                speed: 25.0,
+                movement: EnemyMovement::Pathfind,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![
+                    (
+                        StateChangeCondition::NearPlayer,
+                        StateTarget::Named("attack"),
+                    ),
+                    (
+                        StateChangeCondition::All(vec![
+                            StateChangeCondition::LineOfSight,
+                            StateChangeCondition::PlayerDistance(160.0, f32::INFINITY),
+                        ]),
+                        StateTarget::Named("burrow"),
+                    ),
+                ],
+                damage_on_exit: None,
+                pattern: None,
+            },
+            EnemyState {
+                name: "attack",
+                animation_id: 1,
+                speed: 0.0,
+                movement: EnemyMovement::Chase,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![(
+                    StateChangeCondition::AnimationFinish,
+                    StateTarget::Named("chase"),
+                )],
+                damage_on_exit: Some(Damage::new(15.0, DamageType::Melee)),
+                pattern: None,
+            },
+            EnemyState {
+                name: "burrow",
+                animation_id: 0,
+                speed: 0.0,
+                movement: EnemyMovement::Burrow,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![(
+                    StateChangeCondition::MovementFinish,
+                    StateTarget::Named("chase"),
+                )],
+                damage_on_exit: None,
+                pattern: None,
+            },
+        ],
+        health: 20.0,
+        cost: 3,
+        loot: vec![(PickupKind::Health, 0.1), (PickupKind::Ammo, 0.15)],
+        death_hazard: None,
+        resistances: vec![(DamageType::Acid, 0.0), (DamageType::Fire, 1.5)],
     };
-    vec![greeno, dog, shooter, bigo]
+    // dog that walks its patrol route until it sees the player, and backs off when hit
+    let hound: EnemyType = EnemyType {
+        name: "hound",
+        states: vec![
+            EnemyState {
+                name: "patrol",
+                animation_id: 2,
+                speed: 40.0,
+                movement: EnemyMovement::Patrol,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![(
+                    StateChangeCondition::LineOfSight,
+                    StateTarget::Named("chase"),
+                )],
+                damage_on_exit: None,
+                pattern: None,
+            },
+            EnemyState {
+                name: "chase",
+                animation_id: 2,
+                speed: 80.0,
+                movement: EnemyMovement::Chase,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![
+                    (
+                        StateChangeCondition::NearPlayer,
+                        StateTarget::Named("attack"),
+                    ),
+                    (StateChangeCondition::TookDamage, StateTarget::Named("flee")),
+                ],
+                damage_on_exit: None,
+                pattern: None,
+            },
+            EnemyState {
+                name: "attack",
+                animation_id: 3,
+                speed: 0.0,
+                movement: EnemyMovement::Chase,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![(
+                    StateChangeCondition::AnimationFinish,
+                    StateTarget::Named("chase"),
+                )],
+                damage_on_exit: Some(
+                    Damage::new(5.0, DamageType::Melee).with(StatusKind::Slow, 1.0),
+                ),
+                pattern: None,
+            },
+            EnemyState {
+                name: "flee",
+                animation_id: 2,
+                speed: 90.0,
+                movement: EnemyMovement::Flee,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![(
+                    StateChangeCondition::Timer(0.6),
+                    StateTarget::Named("chase"),
+                )],
+                damage_on_exit: None,
+                pattern: None,
+            },
+        ],
+        health: 9.0,
+        cost: 2,
+        loot: vec![(PickupKind::Ammo, 0.1)],
+        death_hazard: None,
+        resistances: vec![(DamageType::Fire, 1.25)],
+    };
+    // shooter that stands its ground, and sometimes teleports away when hit
+    let blinker: EnemyType = EnemyType {
+        name: "blinker",
+        states: vec![
+            EnemyState {
+                name: "shoot",
+                animation_id: 4,
+                speed: 0.0,
+                movement: EnemyMovement::Chase,
+                projectile_firing: ProjectileFiring::Forwards(&FROST_BALL),
+                transitions: vec![
+                    (
+                        StateChangeCondition::TookDamage,
+                        StateTarget::Random(vec![("blink", 1.0), ("shoot", 2.0)]),
+                    ),
+                    (
+                        StateChangeCondition::AnimationFinish,
+                        StateTarget::Named("shoot"),
+                    ),
+                ],
+                damage_on_exit: None,
+                pattern: None,
+            },
+            EnemyState {
+                name: "blink",
+                animation_id: 4,
+                speed: 0.0,
+                movement: EnemyMovement::Teleport,
+                projectile_firing: ProjectileFiring::None,
+                transitions: vec![(
+                    StateChangeCondition::MovementFinish,
+                    StateTarget::Named("shoot"),
+                )],
+                damage_on_exit: None,
+                pattern: None,
+            },
+        ],
+        health: 9.0,
+        cost: 3,
+        loot: vec![
+            (PickupKind::Ammo, 0.2),
+            (PickupKind::FireRateBoost, 0.05),
+            (PickupKind::Modifier(WeaponModifier::Focused), 0.03),
+        ],
+        death_hazard: None,
+        resistances: vec![(DamageType::Energy, 0.75)],
+    };
+    let enemies = vec![greeno, dog, shooter, bigo, brute, lurker, hound, blinker];
+    for ty in &enemies {
+        ty.check_transitions();
+    }
+    enemies
 });
 
+static NEXT_ENEMY_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Spawns an enemy placed in the map, along with the extra copies higher difficulties add, which
+/// spread out around it without going into walls. The player set them off from `hear_from`, so
+/// they come looking even without seeing them.
+pub fn spawn_placed(
+    ty: &'static EnemyType,
+    pos: Vec2,
+    world: &World,
+    hear_from: Vec2,
+) -> Vec<Enemy> {
+    (0..settings().spawn_count(1))
+        .map(|index| {
+            let offset = if index == 0 {
+                Vec2::ZERO
+            } else {
+                vec2(rand::gen_range(-4.0, 4.0), rand::gen_range(-4.0, 4.0))
+            };
+            let pos = if world.is_solid(pos + offset) {
+                pos
+            } else {
+                pos + offset
+            };
+            let mut enemy = Enemy::new(ty, pos);
+            enemy.roll_elite(world.elite_chance);
+            enemy.hear(hear_from);
+            enemy
+        })
+        .collect()
+}
+
 pub struct Enemy {
+    pub id: u32,
     pub ty: &'static EnemyType,
     pub pos: Vec2,
     pub health: f32,
@@ -134,13 +604,34 @@ pub struct Enemy {
     pub velocity: Vec2,
     pub emerging: bool,
     pub state: usize,
+    /// Whether the enemy was hit since entering its current state
+    pub took_damage: bool,
+    pub awareness: Awareness,
+    /// Which way the enemy is circling while strafing or orbiting, either 1 or -1
+    pub strafe_side: f32,
+    pub wander: Vec2,
+    /// Path being patrolled and the index of the point being walked to
+    pub patrol: Option<(usize, usize)>,
+    /// Whether the enemy has burrowed or teleported since entering its current state
+    pub relocated: bool,
+    /// Where the player was last seen or heard
+    pub last_seen: Vec2,
+    /// Time since the enemy became alert
+    pub noticed: f32,
+    pub pattern: Option<PatternRunner>,
+    pub boss: Option<Boss>,
+    pub statuses: Statuses,
+    pub elite: Option<EliteModifier>,
+    /// Absorbs damage before health does
+    pub shield: f32,
 }
 impl Enemy {
     pub fn new(ty: &'static EnemyType, pos: Vec2) -> Self {
         Self {
+            id: NEXT_ENEMY_ID.fetch_add(1, Ordering::Relaxed),
             ty,
             pos,
-            health: ty.health,
+            health: settings().enemy_health(ty.health),
             animation_time: 0.0,
             direction: vec2(1.0, 0.0),
             path: None,
@@ -148,28 +639,172 @@ impl Enemy {
             emerging: true,
             velocity: Vec2::ZERO,
             state: 0,
+            took_damage: false,
+            awareness: Awareness::Idle,
+            wander: Vec2::ZERO,
+            patrol: None,
+            relocated: false,
+            strafe_side: if rand::gen_range(0, 2) == 0 {
+                1.0
+            } else {
+                -1.0
+            },
+            last_seen: pos,
+            noticed: 0.0,
+            pattern: None,
+            boss: None,
+            statuses: Statuses::default(),
+            elite: None,
+            shield: 0.0,
+        }
+    }
+    pub fn new_boss(ty: &'static BossType, pos: Vec2) -> Self {
+        let mut new = Self::new(&ty.phases[0].ty, pos);
+        new.boss = Some(Boss {
+            ty,
+            phase: 0,
+            arena: None,
+        });
+        new
+    }
+    /// Makes the enemy an elite with a random modifier, `chance` of the time
+    pub fn roll_elite(&mut self, chance: f32) {
+        if self.boss.is_some() || rand::gen_range(0.0, 1.0) >= chance {
+            return;
         }
+        let elite = EliteModifier::ALL[rand::gen_range(0, EliteModifier::ALL.len())];
+        self.elite = Some(elite);
+        self.health = self.max_health();
+        if elite == EliteModifier::Shielded {
+            self.shield = self.base_health() * ELITE_SHIELD;
+        }
+    }
+    /// Health of the enemy's type, scaled by difficulty
+    fn base_health(&self) -> f32 {
+        settings().enemy_health(self.ty.health)
+    }
+    pub fn max_health(&self) -> f32 {
+        self.base_health() * self.elite.map_or(1.0, |f| f.health())
+    }
+    /// Multiplier of the damage the enemy deals
+    fn damage_scale(&self) -> f32 {
+        self.elite.map_or(1.0, |f| f.damage())
+    }
+    fn resistance(&self, ty: DamageType) -> f32 {
+        if self.elite == Some(EliteModifier::Acidic) && ty == DamageType::Acid {
+            return 0.0;
+        }
+        self.ty.resistance(ty)
+    }
+    /// Hazard left on the floor where the enemy dies
+    pub fn death_hazard(&self) -> Option<&'static TileEntityKind> {
+        self.ty
+            .death_hazard
+            .or((self.elite == Some(EliteModifier::Acidic)).then_some(&ACID))
+    }
+    /// Runs the death effect of the enemy's elite modifier, returning the enemies it splits into
+    pub fn elite_death(&self, projectiles: &mut Vec<Projectile>) -> Vec<Enemy> {
+        match self.elite {
+            Some(EliteModifier::Splitting) => [-1.0, 1.0]
+                .into_iter()
+                .map(|side| {
+                    let mut new =
+                        Enemy::new(self.ty, self.pos + self.direction.perp() * side * 6.0);
+                    new.health = self.base_health() / 2.0;
+                    // skip climbing out of a hole
+                    new.animation_time = HOLE_TIME;
+                    new.awareness = Awareness::Alert;
+                    new
+                })
+                .collect(),
+            Some(EliteModifier::Volatile) => {
+                for index in 0..ELITE_BURST {
+                    let angle = index as f32 / ELITE_BURST as f32 * 2.0 * PI;
+                    let mut projectile =
+                        Projectile::new(&ALIEN_BALL, self.pos, Vec2::from_angle(angle), false);
+                    projectile.damage *= self.damage_scale();
+                    projectiles.push(projectile);
+                }
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+    /// Starts the bullet pattern of the current state, if it has one
+    fn start_pattern(&mut self) {
+        self.pattern = self
+            .current_state()
+            .pattern
+            .map(|name| PatternRunner::new(name, self.pos, self.direction));
     }
     fn current_state(&self) -> &'static EnemyState {
         &self.ty.states[self.state % self.ty.states.len()]
     }
+    #[expect(clippy::too_many_arguments)]
     pub fn update(
         &mut self,
         delta_time: f32,
         player: &mut Player,
+        companions: &mut [Companion],
         world: &World,
         assets: &Assets,
         projectiles: &mut Vec<Projectile>,
+        particles: &mut Particles,
     ) {
+        // status ticks bypass `damage`, so they don't count as being hit for `TookDamage`
+        for (amount, ty) in self.statuses.update(delta_time) {
+            self.health -= amount * self.resistance(ty);
+        }
+        // stunned and frozen enemies stop mid animation
+        if self.statuses.stunned() {
+            return;
+        }
         self.animation_time += delta_time;
         if self.emerging && self.animation_time < HOLE_TIME {
+            if self.animation_time < HOLE_EMERGE_TIME + 0.3 {
+                particles.emit(
+                    &EMERGE_DEBRIS,
+                    self.pos + vec2(0.0, 8.0),
+                    vec2(0.0, -1.0),
+                    delta_time,
+                );
+            }
             return;
         } else if self.emerging {
             self.emerging = false;
+            self.start_pattern();
+        }
+        if let Some(boss) = &mut self.boss
+            && let Some(phase) = boss.ty.phases.get(boss.phase + 1)
+            && self.health <= phase.health * settings().enemy_health(boss.ty.health)
+        {
+            boss.phase += 1;
+            self.ty = &phase.ty;
+            self.state = 0;
+            self.animation_time = 0.0;
+            self.took_damage = false;
+            self.relocated = false;
+            self.start_pattern();
+            particles.emit(&EXPLOSION, self.pos, Vec2::ZERO, delta_time);
+        }
+        self.update_awareness(player, world, delta_time);
+        let idle = matches!(self.awareness, Awareness::Idle);
+        if idle
+            && !matches!(
+                self.current_state().movement,
+                EnemyMovement::Wander | EnemyMovement::Patrol
+            )
+        {
+            return;
         }
-        let delta = player.pos - self.pos;
+        let (victim, victim_velocity) = self.victim(player, companions, world);
+        let delta = victim - self.pos;
         let mut hit_wall = false;
-        let mut target = player.pos + 8.0;
+        let mut target = match self.awareness {
+            Awareness::Search(_) if self.pos.distance(self.last_seen) < 4.0 => self.pos,
+            Awareness::Search(_) => self.last_seen,
+            _ => victim + 8.0,
+        };
         if delta.length() > 0.0 {
             self.time_til_pathfind -= delta_time;
 
@@ -177,9 +812,7 @@ impl Enemy {
                 && (self.path.is_none() || self.time_til_pathfind <= 0.0)
             {
                 self.time_til_pathfind = 2.0;
-                self.path = world
-                    .pathfind(self.pos, player.pos + 8.0)
-                    .map(|f| f.0.into());
+                self.path = world.pathfind(self.pos, victim + 8.0).map(|f| f.0.into());
             }
             if let Some(path) = &mut self.path
                 && let Some((x, y)) = path.get(1)
@@ -191,78 +824,419 @@ impl Enemy {
                 target = next;
             }
         }
-        if matches!(self.current_state().movement, EnemyMovement::Straight) {
-            target = self.pos + self.direction;
+        let to_target = target - self.pos;
+        match self.current_state().movement {
+            EnemyMovement::Straight => target = self.pos + self.direction,
+            EnemyMovement::Flee => target = self.pos - to_target.normalize_or_zero(),
+            EnemyMovement::Orbit(radius) if to_target.length() > 0.0 => {
+                let closer = ((to_target.length() - radius) / 32.0).clamp(-1.0, 1.0);
+                target = self.pos
+                    + (to_target.normalize() * closer
+                        + to_target.perp().normalize() * self.strafe_side)
+                        .normalize_or_zero();
+            }
+            EnemyMovement::Wander => {
+                if self.wander == Vec2::ZERO || rand::gen_range(0.0, 1.0) < delta_time * 0.5 {
+                    self.wander = Vec2::from_angle(rand::gen_range(0.0, 2.0 * PI));
+                }
+                target = self.pos + self.wander;
+            }
+            EnemyMovement::Patrol => target = self.patrol_target(world),
+            EnemyMovement::Burrow => {
+                target = self.pos;
+                if !self.relocated {
+                    particles.emit(
+                        &EMERGE_DEBRIS,
+                        self.pos + vec2(0.0, 8.0),
+                        vec2(0.0, -1.0),
+                        delta_time,
+                    );
+                    if self.animation_time >= BURROW_TIME {
+                        self.relocate(player, world);
+                        self.emerging = true;
+                        self.animation_time = 0.0;
+                    }
+                }
+            }
+            EnemyMovement::Teleport => {
+                target = self.pos;
+                if !self.relocated && self.animation_time >= TELEPORT_TIME {
+                    particles.emit(&TELEPORT_SPARKS, self.pos, Vec2::ZERO, delta_time);
+                    self.relocate(player, world);
+                    particles.emit(&TELEPORT_SPARKS, self.pos, Vec2::ZERO, delta_time);
+                    self.animation_time = 0.0;
+                }
+            }
+            _ => {}
+        }
+        let to_player = victim + 8.0 - self.pos;
+        if let EnemyMovement::Strafe(keep_distance) = self.current_state().movement
+            && matches!(self.awareness, Awareness::Alert)
+            && to_player.length() > 0.0
+        {
+            if rand::gen_range(0.0, 1.0) < delta_time * 0.4 {
+                self.strafe_side = -self.strafe_side;
+            }
+            let closer = ((to_player.length() - keep_distance) / 32.0).clamp(-1.0, 1.0);
+            target = self.pos
+                + (to_player.normalize() * closer
+                    + to_player.perp().normalize() * self.strafe_side)
+                    .normalize_or_zero();
         }
         let distance = target.distance_squared(self.pos);
         if distance > 0.0 && !matches!(self.current_state().movement, EnemyMovement::None) {
             self.direction = (target - self.pos).normalize();
-            self.velocity = (target - self.pos).normalize() * self.current_state().speed;
+            self.velocity = (target - self.pos).normalize()
+                * self.current_state().speed
+                * self.statuses.speed()
+                * self.elite.map_or(1.0, |f| f.speed());
             let v = self.velocity;
             self.pos = update_physicsbody(self.pos, &mut self.velocity, delta_time, world);
             if self.velocity.length_squared() < v.length_squared() {
                 hit_wall = true;
             }
         }
-
-        if match self.current_state().change_state {
-            StateChangeCondition::Always => true,
-            StateChangeCondition::Never => false,
-            StateChangeCondition::AnimationFinish => {
-                matches!(
-                    self.current_state().change_state,
-                    StateChangeCondition::AnimationFinish
-                ) && self.animation_time * 1000.0
-                    >= assets.enemies.animations[self.current_state().animation_id].total_length
-                        as f32
+        let (mut pull, _) = world.breach_pull(self.pos);
+        if pull != Vec2::ZERO {
+            self.pos = update_physicsbody(self.pos, &mut pull, delta_time, world);
+        }
+        if let EnemyMovement::Strafe(_) = self.current_state().movement
+            && to_player.length() > 0.0
+        {
+            self.direction = to_player.normalize();
+            if hit_wall {
+                self.strafe_side = -self.strafe_side;
             }
-            StateChangeCondition::NearPlayer => player.pos.distance_squared(self.pos) < 144.0,
-            StateChangeCondition::HitWall => {
-                hit_wall || player.pos.distance_squared(self.pos) < 144.0
+        }
+        if hit_wall {
+            match self.current_state().movement {
+                EnemyMovement::Orbit(_) => self.strafe_side = -self.strafe_side,
+                EnemyMovement::Wander => self.wander = Vec2::ZERO,
+                _ => {}
             }
-        } {
-            if let Some(damage) = self.current_state().damage_on_exit
-                && player.pos.distance_squared(self.pos) < 144.0
-            {
-                player.health -= damage;
+        }
+        if idle {
+            return;
+        }
+
+        let fired = projectiles.len();
+        let transition = self
+            .current_state()
+            .transitions
+            .iter()
+            .find(|(condition, _)| self.condition_met(condition, victim, world, assets, hit_wall));
+        if let Some((_, target)) = transition {
+            if let Some(damage) = self.current_state().damage_on_exit {
+                let damage = Damage {
+                    amount: settings().enemy_damage(damage.amount * self.damage_scale()),
+                    ..damage
+                };
+                if player.pos.distance_squared(self.pos) < 144.0 {
+                    player.hit(damage);
+                }
+                for companion in companions
+                    .iter_mut()
+                    .filter(|f| f.pos.distance_squared(self.pos) < 144.0)
+                {
+                    companion.hit(damage);
+                }
             }
             match &self.current_state().projectile_firing {
                 ProjectileFiring::None => {}
                 ProjectileFiring::Forwards(projectile) => {
-                    let new = Projectile {
-                        ty: projectile,
-                        pos: self.pos,
-                        dir: self.direction,
-                        time: 0.0,
-                        friendly: false,
-                    };
-                    projectiles.push(new);
+                    if world.line_of_sight(self.pos, victim + 8.0) {
+                        projectiles.push(Projectile::new(
+                            projectile,
+                            self.pos,
+                            self.direction,
+                            false,
+                        ));
+                    }
+                }
+                ProjectileFiring::Aimed {
+                    projectile,
+                    lead,
+                    spread,
+                } => {
+                    if world.line_of_sight(self.pos, victim + 8.0) {
+                        let aim = lead_target(
+                            self.pos,
+                            victim + 8.0,
+                            victim_velocity * *lead,
+                            settings().hostile_projectile_speed(projectile.speed),
+                        ) - self.pos;
+                        let angle = aim.to_angle() + rand::gen_range(-*spread, *spread);
+                        projectiles.push(Projectile::new(
+                            projectile,
+                            self.pos,
+                            Vec2::from_angle(angle),
+                            false,
+                        ));
+                    }
                 }
                 ProjectileFiring::Around(projectile, amt) => {
                     let angle = 2.0 * PI / *amt as f32;
                     for i in 0..*amt {
                         let angle = angle * i as f32 + self.direction.to_angle();
-                        let new = Projectile {
-                            ty: projectile,
-                            pos: self.pos,
-                            dir: Vec2::from_angle(angle),
-                            time: 0.0,
-                            friendly: false,
-                        };
-                        projectiles.push(new);
+                        projectiles.push(Projectile::new(
+                            projectile,
+                            self.pos,
+                            Vec2::from_angle(angle),
+                            false,
+                        ));
                     }
                 }
             }
-            self.state += 1;
+            self.state = match target {
+                StateTarget::Next => (self.state + 1) % self.ty.states.len(),
+                StateTarget::Named(name) => self.state_index(name),
+                StateTarget::Random(choices) => {
+                    let total: f32 = choices.iter().map(|(_, weight)| weight).sum();
+                    let mut roll = rand::gen_range(0.0, total);
+                    let (name, _) = choices
+                        .iter()
+                        .find(|(_, weight)| {
+                            roll -= weight;
+                            roll < 0.0
+                        })
+                        .unwrap_or(choices.last().unwrap());
+                    self.state_index(name)
+                }
+            };
             self.animation_time = 0.0;
+            self.took_damage = false;
+            self.relocated = false;
+            self.start_pattern();
+        }
+        let progress = self.animation_time * 1000.0
+            / assets.enemies.animations[self.current_state().animation_id].total_length as f32;
+        if let Some(pattern) = &mut self.pattern {
+            pattern.pos = self.pos;
+            if !pattern.update(delta_time, victim + 8.0, Some(progress), projectiles) {
+                self.pattern = None;
+            }
+        }
+        for projectile in &mut projectiles[fired..] {
+            projectile.damage *= self.damage_scale();
+        }
+    }
+    fn condition_met(
+        &self,
+        condition: &StateChangeCondition,
+        victim: Vec2,
+        world: &World,
+        assets: &Assets,
+        hit_wall: bool,
+    ) -> bool {
+        let delta = victim + 8.0 - self.pos;
+        match condition {
+            StateChangeCondition::Always => true,
+            StateChangeCondition::Never => false,
+            StateChangeCondition::AnimationFinish => {
+                self.animation_time * 1000.0
+                    >= assets.enemies.animations[self.current_state().animation_id].total_length
+                        as f32
+            }
+            StateChangeCondition::NearPlayer => victim.distance_squared(self.pos) < 144.0,
+            StateChangeCondition::HitWall => hit_wall || victim.distance_squared(self.pos) < 144.0,
+            StateChangeCondition::PatternFinish => self.pattern.is_none(),
+            StateChangeCondition::Timer(time) => self.animation_time >= *time,
+            StateChangeCondition::HealthBelow(amount) => self.health < self.max_health() * amount,
+            StateChangeCondition::LineOfSight => world.line_of_sight(self.pos, victim + 8.0),
+            StateChangeCondition::PlayerDistance(min, max) => {
+                (*min..=*max).contains(&delta.length())
+            }
+            StateChangeCondition::TookDamage => self.took_damage,
+            StateChangeCondition::MovementFinish => {
+                self.relocated
+                    && match self.current_state().movement {
+                        EnemyMovement::Burrow => !self.emerging,
+                        EnemyMovement::Teleport => self.animation_time >= TELEPORT_TIME,
+                        _ => true,
+                    }
+            }
+            StateChangeCondition::All(conditions) => conditions
+                .iter()
+                .all(|f| self.condition_met(f, victim, world, assets, hit_wall)),
+        }
+    }
+    /// Top left corner and velocity of who the enemy goes for: the player, or a following
+    /// companion it can see that is closer
+    fn victim(&self, player: &Player, companions: &[Companion], world: &World) -> (Vec2, Vec2) {
+        let player_distance = player.pos.distance(self.pos);
+        companions
+            .iter()
+            .filter(|f| {
+                f.following
+                    && f.pos.distance(self.pos) < player_distance
+                    && world.line_of_sight(self.pos, f.pos + 8.0)
+            })
+            .min_by(|a, b| {
+                a.pos
+                    .distance_squared(self.pos)
+                    .total_cmp(&b.pos.distance_squared(self.pos))
+            })
+            .map_or((player.pos, player.velocity), |f| (f.pos, f.velocity))
+    }
+    fn update_awareness(&mut self, player: &Player, world: &World, delta_time: f32) {
+        let target = player.pos + 8.0;
+        let sees = self.boss.is_some()
+            || (self.pos.distance(target) < SIGHT_RANGE && world.line_of_sight(self.pos, target));
+        self.noticed += delta_time;
+        if sees {
+            if !matches!(self.awareness, Awareness::Alert) {
+                self.noticed = 0.0;
+            }
+            self.awareness = Awareness::Alert;
+            self.last_seen = target;
+            return;
+        }
+        match &mut self.awareness {
+            Awareness::Alert => self.awareness = Awareness::Search(SEARCH_TIME),
+            Awareness::Search(time) => {
+                *time -= delta_time;
+                if *time <= 0.0 {
+                    self.awareness = Awareness::Idle;
+                }
+            }
+            Awareness::Idle => {}
+        }
+    }
+    /// Makes the enemy go looking for a noise, unless it can already see the player
+    pub fn hear(&mut self, pos: Vec2) {
+        if !matches!(self.awareness, Awareness::Alert) {
+            self.awareness = Awareness::Search(SEARCH_TIME);
+            self.last_seen = pos;
+        }
+    }
+    /// Picks the next point to walk to on the nearest patrol path
+    fn patrol_target(&mut self, world: &World) -> Vec2 {
+        if self.patrol.is_none() {
+            self.patrol = world
+                .patrols
+                .iter()
+                .enumerate()
+                .flat_map(|(path, points)| {
+                    points
+                        .iter()
+                        .enumerate()
+                        .map(move |(point, pos)| (path, point, *pos))
+                })
+                .min_by(|a, b| {
+                    a.2.distance_squared(self.pos)
+                        .total_cmp(&b.2.distance_squared(self.pos))
+                })
+                .map(|(path, point, _)| (path, point));
+        }
+        let Some((path, point)) = &mut self.patrol else {
+            return self.pos;
+        };
+        let points = &world.patrols[*path];
+        if points[*point].distance(self.pos) < 4.0 {
+            *point = (*point + 1) % points.len();
+        }
+        points[*point]
+    }
+    /// Moves somewhere open near the player that they can see
+    fn relocate(&mut self, player: &Player, world: &World) {
+        let center = player.pos + 8.0;
+        for _ in 0..16 {
+            let pos = center
+                + Vec2::from_angle(rand::gen_range(0.0, 2.0 * PI)) * rand::gen_range(48.0, 96.0);
+            // the enemy's position is the middle of its body
+            let open = [
+                vec2(-8.0, -8.0),
+                vec2(7.0, -8.0),
+                vec2(-8.0, 7.0),
+                vec2(7.0, 7.0),
+            ]
+            .iter()
+            .all(|corner| !world.is_solid(pos + *corner));
+            if open && world.line_of_sight(center, pos) {
+                self.pos = pos;
+                break;
+            }
+        }
+        self.relocated = true;
+    }
+    fn state_index(&self, name: &str) -> usize {
+        self.ty
+            .states
+            .iter()
+            .position(|f| f.name == name)
+            .expect("transitions are checked when the enemy tables are built")
+    }
+    /// Deals damage to the enemy, going through its shield first. Only damage from an `attacker`,
+    /// rather than from hazards, counts as the enemy being hit for its state changes.
+    pub fn damage(&mut self, amount: f32, attacker: bool) {
+        let absorbed = amount.min(self.shield);
+        self.shield -= absorbed;
+        self.health -= amount - absorbed;
+        self.took_damage |= attacker;
+    }
+    /// Deals typed damage, scaled by the enemy's resistance to it, and inflicts its status effect
+    /// unless the enemy is immune
+    pub fn hit(&mut self, damage: Damage, attacker: bool) {
+        let resistance = self.resistance(damage.ty);
+        self.damage(damage.amount * resistance, attacker);
+        if resistance > 0.0
+            && let Some(effect) = damage.effect
+        {
+            self.statuses.apply(effect);
         }
     }
+    /// Rolls the loot table of this enemy
+    pub fn drop_loot(&self) -> Vec<Pickup> {
+        self.ty
+            .loot
+            .iter()
+            .filter(|(_, chance)| rand::gen_range(0.0, 1.0) < *chance)
+            .map(|(kind, _)| {
+                let offset = vec2(rand::gen_range(-6.0, 6.0), rand::gen_range(-6.0, 6.0));
+                Pickup::new(*kind, self.pos + offset)
+            })
+            .collect()
+    }
     pub fn draw(&mut self, assets: &Assets) {
-        if self.emerging && self.animation_time < HOLE_TIME {
+        let palette = self.elite.zip(assets.palette.as_ref());
+        let tint = match (self.elite, palette) {
+            (Some(elite), None) => elite.color(),
+            _ => WHITE,
+        };
+        if let Some((elite, material)) = palette {
+            gl_use_material(material);
+            material.set_uniform("Hue", elite.hue());
+        }
+        self.draw_sprite(assets, tint);
+        if palette.is_some() {
+            gl_use_default_material();
+        }
+    }
+    fn draw_sprite(&mut self, assets: &Assets, tint: Color) {
+        let texture = assets.enemies.animations[self.current_state().animation_id]
+            .get_at_time((self.animation_time * 1000.0) as u32);
+        let params = DrawTextureParams {
+            flip_x: self.direction.x > 0.0,
+            ..Default::default()
+        };
+        let movement = &self.current_state().movement;
+        let sinking = matches!(movement, EnemyMovement::Burrow) && !self.relocated;
+        if (self.emerging && self.animation_time < HOLE_TIME) || sinking {
             let max_hole_diameter = 20.0;
-            let diameter = (self.animation_time / HOLE_EMERGE_TIME * max_hole_diameter)
-                .min(max_hole_diameter)
-                .floor();
+            let (diameter, rise) = if sinking {
+                let amt = (self.animation_time / BURROW_TIME).min(1.0);
+                (max_hole_diameter, Some(1.0 - amt * amt))
+            } else {
+                let diameter = (self.animation_time / HOLE_EMERGE_TIME * max_hole_diameter)
+                    .min(max_hole_diameter)
+                    .floor();
+                let rise = (self.animation_time > HOLE_EMERGE_TIME).then(|| {
+                    let amt =
+                        (self.animation_time - HOLE_EMERGE_TIME) / (HOLE_TIME - HOLE_EMERGE_TIME);
+                    (amt - 1.0).powi(5) + 1.0
+                });
+                (diameter, rise)
+            };
             draw_ellipse(
                 self.pos.x.floor(),
                 self.pos.y.floor() + 8.0,
@@ -271,35 +1245,48 @@ impl Enemy {
                 0.0,
                 BLACK,
             );
-            if self.animation_time > HOLE_EMERGE_TIME {
-                let amt = (self.animation_time - HOLE_EMERGE_TIME) / (HOLE_TIME - HOLE_EMERGE_TIME);
-                let amt = (amt - 1.0).powi(5) + 1.0;
+            if let Some(amt) = rise {
                 let pos = self.pos.floor() + vec2(0.0, 13.0 - amt * 13.0);
                 draw_texture_ex(
-                    assets.enemies.animations[self.current_state().animation_id]
-                        .get_at_time((self.animation_time * 1000.0) as u32),
+                    texture,
                     pos.x.floor() - 16.0,
                     pos.y.floor() - 16.0,
-                    WHITE,
-                    DrawTextureParams {
-                        flip_x: self.direction.x > 0.0,
-                        ..Default::default()
-                    },
+                    tint,
+                    params,
                 );
             }
             return;
         }
+        let alpha = match movement {
+            EnemyMovement::Teleport if self.relocated => self.animation_time / TELEPORT_TIME,
+            EnemyMovement::Teleport => 1.0 - self.animation_time / TELEPORT_TIME,
+            _ => 1.0,
+        };
         draw_texture_ex(
-            assets.enemies.animations[self.current_state().animation_id]
-                .get_at_time((self.animation_time * 1000.0) as u32),
+            texture,
             self.pos.x.floor() - 16.0,
             self.pos.y.floor() - 16.0,
-            WHITE,
-            DrawTextureParams {
-                flip_x: self.direction.x > 0.0,
-                ..Default::default()
-            },
+            multiply(tint, self.statuses.tint()).with_alpha(alpha.clamp(0.0, 1.0)),
+            params,
         );
+        let top = self.pos.floor() - vec2(0.0, 28.0);
+        match self.awareness {
+            Awareness::Alert if self.noticed < 0.8 => {
+                draw_rectangle(top.x - 1.0, top.y - 6.0, 2.0, 4.0, ALERT_COLOR);
+                draw_rectangle(top.x - 1.0, top.y - 1.0, 2.0, 2.0, ALERT_COLOR);
+            }
+            Awareness::Search(_) => {
+                draw_rectangle(top.x - 2.0, top.y - 7.0, 4.0, 1.0, SEARCH_COLOR);
+                draw_rectangle(top.x + 2.0, top.y - 6.0, 1.0, 2.0, SEARCH_COLOR);
+                draw_rectangle(top.x, top.y - 4.0, 2.0, 1.0, SEARCH_COLOR);
+                draw_rectangle(top.x, top.y - 3.0, 1.0, 1.0, SEARCH_COLOR);
+                draw_rectangle(top.x, top.y - 1.0, 1.0, 1.0, SEARCH_COLOR);
+            }
+            _ => {}
+        }
+        if self.boss.is_some() {
+            return;
+        }
         let width = 25.0;
         let height = 4.0;
         let pos = self.pos.floor() - 16.0 + vec2(0.0, -4.0) + (32.0 - width) / 2.0;
@@ -307,12 +1294,73 @@ impl Enemy {
         draw_rectangle(
             pos.x,
             pos.y,
-            self.health / self.ty.health * width,
+            self.health / self.max_health() * width,
             height,
             HEALTHBAR_COLOR,
         );
+        if self.shield > 0.0 {
+            draw_rectangle(
+                pos.x,
+                pos.y + height - 1.0,
+                self.shield / (self.base_health() * ELITE_SHIELD) * width,
+                1.0,
+                SHIELD_COLOR,
+            );
+        }
+        if let Some(elite) = self.elite {
+            let size = measure_text(elite.name(), None, 16, 0.5);
+            draw_text_ex(
+                elite.name(),
+                (self.pos.x - size.width / 2.0).floor(),
+                pos.y - 2.0,
+                TextParams {
+                    font_size: 16,
+                    font_scale: 0.5,
+                    color: elite.color(),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+/// Where a target moving at `velocity` will be when a projectile fired now at `speed` reaches it
+fn lead_target(from: Vec2, target: Vec2, velocity: Vec2, speed: f32) -> Vec2 {
+    let delta = target - from;
+    // solve |delta + velocity * t| = speed * t for the earliest t
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2.0 * delta.dot(velocity);
+    let c = delta.length_squared();
+    let time = if a.abs() < 0.001 {
+        -c / b
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return target;
+        }
+        let root = discriminant.sqrt();
+        [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+            .into_iter()
+            .filter(|t| *t > 0.0)
+            .fold(f32::INFINITY, f32::min)
+    };
+    if time.is_finite() && time > 0.0 {
+        target + velocity * time
+    } else {
+        target
     }
 }
 pub const HEALTHBAR_COLOR: Color = Color::from_hex(0x39741f);
+const ALERT_COLOR: Color = Color::from_hex(0xf04030);
+const SEARCH_COLOR: Color = Color::from_hex(0xf0d040);
+const SIGHT_RANGE: f32 = 240.0;
+/// How far away enemies hear the player's gunfire from
+pub const HEARING_RANGE: f32 = 200.0;
+const SEARCH_TIME: f32 = 5.0;
+const BURROW_TIME: f32 = 0.5;
+const TELEPORT_TIME: f32 = 0.3;
 const HOLE_EMERGE_TIME: f32 = 0.7;
 const HOLE_TIME: f32 = 1.8;
+/// Shield of shielded elites, as a fraction of their type's health
+const ELITE_SHIELD: f32 = 0.5;
+/// Projectiles volatile elites burst into on death
+const ELITE_BURST: u8 = 8;
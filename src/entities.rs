@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::{
+    assets::{TmxObject, World},
+    companions::CompanionKind,
+    enemy::{ENEMIES, Enemy, EnemyType, spawn_placed},
+    pickups::{Keycard, PickupKind},
+    player::Player,
+};
+
+/// Names used in object properties, in the same order as their tiles in the "Interactable" layer
+const WEAPON_NAMES: [&str; 3] = ["gun", "rifle", "shotgun"];
+const MODIFIER_NAMES: [&str; 6] = [
+    "piercing",
+    "bouncing",
+    "homing",
+    "explosive",
+    "focused",
+    "extra_projectile",
+];
+const PICKUP_NAMES: [&str; 5] = [
+    "ammo",
+    "health",
+    "damage_boost",
+    "fire_rate_boost",
+    "shield",
+];
+
+/// Finds the "Interactable" tile that an object from the "Entities" layer stands in for
+pub fn object_tile(object: &TmxObject) -> Option<i16> {
+    let index = |names: &[&str], property: &str| {
+        let name = object.properties.get(property)?;
+        let index = names.iter().position(|f| f == name);
+        Some(index.unwrap_or_else(|| panic!("no {property} named {name}")) as i16)
+    };
+    Some(match object.class.as_str() {
+        "player_spawn" => 16,
+        "light" => 17,
+        "weapon_locker" => 112 + index(&WEAPON_NAMES, "weapon")?,
+        "modifier_locker" => 115 + index(&MODIFIER_NAMES, "modifier")?,
+        "pickup" => 130 + index(&PICKUP_NAMES, "kind")?,
+        "pod_door" => 128,
+        "escape_pod" => 129,
+        _ => return None,
+    })
+}
+
+/// Finds the pickup that a "keycard" object from the "Entities" layer places
+pub fn object_keycard(object: &TmxObject) -> Option<PickupKind> {
+    (object.class == "keycard")
+        .then(|| PickupKind::Keycard(Keycard::from_name(&object.properties["color"])))
+}
+
+/// Companion placed by an Entities object, from its class
+pub fn object_companion(object: &TmxObject) -> Option<CompanionKind> {
+    CompanionKind::from_name(&object.class)
+}
+
+struct EnemyPlacement {
+    ty: &'static EnemyType,
+    pos: Vec2,
+    wave: u32,
+}
+/// Area that spawns every enemy of its wave once the player walks into it
+struct Trigger {
+    rect: Rect,
+    wave: u32,
+    fired: bool,
+}
+/// Area that removes the tile entities of a linked object once the player walks into it with every
+/// enemy of its wave dead
+struct Opener {
+    rect: Rect,
+    door: u32,
+    wave: u32,
+}
+
+/// Entities placed as objects in the map's "Entities" layer, rather than as tiles
+#[derive(Default)]
+pub struct MapObjects {
+    enemies: Vec<EnemyPlacement>,
+    triggers: Vec<Trigger>,
+    openers: Vec<Opener>,
+    /// Ids of the enemies spawned for each wave that has been triggered
+    spawned: HashMap<u32, Vec<u32>>,
+}
+impl MapObjects {
+    /// Reads every object that isn't a stand-in for a tile or a tile entity
+    pub fn load(objects: &[TmxObject]) -> Self {
+        let mut new = Self::default();
+        let wave = |object: &TmxObject| {
+            object
+                .properties
+                .get("wave")
+                .map_or(0, |f| f.parse().unwrap())
+        };
+        for object in objects {
+            let rect = Rect::new(object.x, object.y, object.width, object.height);
+            match object.class.as_str() {
+                "enemy" => {
+                    let name = &object.properties["enemy"];
+                    let ty = ENEMIES
+                        .iter()
+                        .find(|f| f.name == name)
+                        .unwrap_or_else(|| panic!("no enemy named {name}"));
+                    new.enemies.push(EnemyPlacement {
+                        ty,
+                        pos: vec2(object.x, object.y),
+                        wave: wave(object),
+                    });
+                }
+                "trigger" => new.triggers.push(Trigger {
+                    rect,
+                    wave: wave(object),
+                    fired: false,
+                }),
+                "opener" => new.openers.push(Opener {
+                    rect,
+                    door: object.properties["door"].parse().unwrap(),
+                    wave: wave(object),
+                }),
+                _ => {}
+            }
+        }
+        new
+    }
+    pub fn update(&mut self, player: &Player, world: &mut World, enemies: &mut Vec<Enemy>) {
+        let center = player.pos + 8.0;
+        for trigger in &mut self.triggers {
+            if trigger.fired || !trigger.rect.contains(center) {
+                continue;
+            }
+            trigger.fired = true;
+            let spawned = self.spawned.entry(trigger.wave).or_default();
+            for placement in self.enemies.iter().filter(|f| f.wave == trigger.wave) {
+                for enemy in spawn_placed(placement.ty, placement.pos, world, center) {
+                    spawned.push(enemy.id);
+                    enemies.push(enemy);
+                }
+            }
+        }
+        for opener in &self.openers {
+            // a wave with enemies has to be set off and beaten first
+            let placed = self.enemies.iter().any(|f| f.wave == opener.wave);
+            let beaten = self
+                .spawned
+                .get(&opener.wave)
+                .is_some_and(|ids| !enemies.iter().any(|f| ids.contains(&f.id)));
+            if opener.rect.contains(center)
+                && (!placed || beaten)
+                && !player.inventory.slots.is_empty()
+            {
+                world.tile_entities.retain(|_, f| f.id != Some(opener.door));
+            }
+        }
+    }
+}
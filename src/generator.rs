@@ -0,0 +1,433 @@
+use std::{collections::HashMap, ops::Range, sync::LazyLock};
+
+use macroquad::{prelude::*, rand::RandGenerator};
+
+use crate::assets::{get_all_chunks, get_layer, get_map_property, get_objects};
+
+/// Tile layers copied from the room templates, in the order they're written out
+const LAYERS: [&str; 6] = [
+    "Background",
+    "Collision",
+    "BackgroundDetails",
+    "Details",
+    "TileEntities",
+    "Interactable",
+];
+const BACKGROUND: usize = 0;
+const COLLISION: usize = 1;
+const DETAILS: usize = 3;
+const INTERACTABLE: usize = 5;
+/// Gids of the enemy spawner tiles in the "Interactable" layer, which are one past the tile index
+const SPAWNER_GIDS: Range<i16> = 97..112;
+/// Gid of the wire that connects spawners to the tiles that set them off
+const WIRE_GID: i16 = 33;
+/// Rooms are laid out on a grid, each centered in a cell this many tiles wide
+const CELL_SIZE: i16 = 36;
+const ROOM_COUNT: usize = 9;
+/// Layouts to try before giving up on a seed
+const MAX_ATTEMPTS: u32 = 100;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    North,
+    East,
+    South,
+    West,
+}
+impl Side {
+    const ALL: [Side; 4] = [Side::North, Side::East, Side::South, Side::West];
+    fn offset(&self) -> (i16, i16) {
+        match self {
+            Side::North => (0, -1),
+            Side::East => (1, 0),
+            Side::South => (0, 1),
+            Side::West => (-1, 0),
+        }
+    }
+    fn opposite(&self) -> Side {
+        Side::ALL[(Side::ALL.iter().position(|f| f == self).unwrap() + 2) % 4]
+    }
+}
+
+type Tiles = HashMap<(i16, i16), i16>;
+
+/// A room from `assets/rooms`, whose "Doors" objects mark the walls that can be opened up
+struct Template {
+    /// "start" for the room with the player spawn, "escape" for the one with the escape pod
+    role: String,
+    difficulty: u32,
+    width: i16,
+    height: i16,
+    layers: Vec<Tiles>,
+    lights: Vec<(Vec2, f32)>,
+    doors: Vec<(Side, Vec<(i16, i16)>)>,
+}
+impl Template {
+    fn parse(xml: &str) -> Self {
+        let layers: Vec<Tiles> = LAYERS
+            .iter()
+            .map(|name| {
+                let mut tiles = HashMap::new();
+                for chunk in get_all_chunks(get_layer(xml, name)) {
+                    for (index, tile) in chunk.tiles.iter().enumerate() {
+                        if *tile != 0 {
+                            let x = (index % 16) as i16 + chunk.x;
+                            let y = (index / 16) as i16 + chunk.y;
+                            tiles.insert((x, y), *tile);
+                        }
+                    }
+                }
+                tiles
+            })
+            .collect();
+        let width = layers[COLLISION].keys().map(|f| f.0).max().unwrap() + 1;
+        let height = layers[COLLISION].keys().map(|f| f.1).max().unwrap() + 1;
+        let doors = get_objects(xml, "Doors")
+            .into_iter()
+            .map(|door| {
+                let (x, y) = ((door.x / 16.0) as i16, (door.y / 16.0) as i16);
+                let (w, h) = ((door.width / 16.0) as i16, (door.height / 16.0) as i16);
+                let side = if y == 0 {
+                    Side::North
+                } else if y + h == height {
+                    Side::South
+                } else if x == 0 {
+                    Side::West
+                } else {
+                    Side::East
+                };
+                let tiles = (x..x + w)
+                    .flat_map(|x| (y..y + h).map(move |y| (x, y)))
+                    .collect();
+                (side, tiles)
+            })
+            .collect();
+        Self {
+            role: get_map_property(xml, "role").unwrap_or("room").to_string(),
+            difficulty: get_map_property(xml, "difficulty").map_or(1, |f| f.parse().unwrap()),
+            width,
+            height,
+            layers,
+            lights: get_objects(xml, "Lights")
+                .into_iter()
+                .map(|f| {
+                    let radius = f
+                        .properties
+                        .get("radius")
+                        .map_or(110.0, |f| f.parse().unwrap());
+                    (vec2(f.x, f.y), radius)
+                })
+                .collect(),
+            doors,
+        }
+    }
+    fn has_doors(&self, sides: &[Side]) -> bool {
+        sides
+            .iter()
+            .all(|side| self.doors.iter().any(|f| f.0 == *side))
+    }
+}
+
+static TEMPLATES: LazyLock<Vec<Template>> = LazyLock::new(|| {
+    [
+        include_str!("../assets/rooms/start.tmx"),
+        include_str!("../assets/rooms/escape.tmx"),
+        include_str!("../assets/rooms/storage.tmx"),
+        include_str!("../assets/rooms/hall.tmx"),
+        include_str!("../assets/rooms/lab.tmx"),
+        include_str!("../assets/rooms/bay.tmx"),
+    ]
+    .into_iter()
+    .map(Template::parse)
+    .collect()
+});
+
+#[cfg(not(target_arch = "wasm32"))]
+const EXPORT_PATH: &str = "generated_station.tmx";
+
+/// Generates a station by stitching room templates together, and returns it as a TMX map.
+/// The same seed always gives the same station.
+pub fn generate(seed: u64) -> Result<String, String> {
+    // a generator of its own, so the game's random numbers aren't reseeded
+    let rng = RandGenerator::new();
+    rng.srand(seed);
+    (0..MAX_ATTEMPTS)
+        .find_map(|_| try_generate(seed, &rng))
+        .ok_or_else(|| format!("no station fits seed {seed} after {MAX_ATTEMPTS} attempts"))
+}
+
+/// Saves a generated station so it can be opened in Tiled
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export(station: &str) {
+    let _ = std::fs::write(EXPORT_PATH, station);
+}
+#[cfg(target_arch = "wasm32")]
+pub fn export(_station: &str) {}
+
+/// Lays out one station, or gives up if the escape pod room doesn't fit anywhere
+fn try_generate(seed: u64, rng: &RandGenerator) -> Option<String> {
+    // grow a tree of rooms on the grid, starting from the spawn room
+    let mut cells = vec![(0, 0)];
+    let mut distances = vec![0];
+    let mut connections = Vec::new();
+    while cells.len() < ROOM_COUNT {
+        let from = rng.gen_range(0, cells.len());
+        let side = Side::ALL[rng.gen_range(0, 4)];
+        let offset = side.offset();
+        let cell = (cells[from].0 + offset.0, cells[from].1 + offset.1);
+        if !cells.contains(&cell) {
+            connections.push((from, cells.len(), side));
+            cells.push(cell);
+            distances.push(distances[from] + 1);
+        }
+    }
+    let sides: Vec<Vec<Side>> = (0..cells.len())
+        .map(|cell| {
+            connections
+                .iter()
+                .filter_map(|(a, b, side)| {
+                    if *a == cell {
+                        Some(*side)
+                    } else if *b == cell {
+                        Some(side.opposite())
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let max_distance = *distances.iter().max().unwrap();
+
+    let escape_template = TEMPLATES.iter().find(|f| f.role == "escape").unwrap();
+    let escape = (1..cells.len())
+        .filter(|f| escape_template.has_doors(&sides[*f]))
+        .max_by_key(|f| distances[*f])?;
+    if distances[escape] < max_distance {
+        return None;
+    }
+
+    let templates: Vec<&Template> = (0..cells.len())
+        .map(|cell| {
+            if cell == 0 {
+                return TEMPLATES.iter().find(|f| f.role == "start").unwrap();
+            }
+            if cell == escape {
+                return escape_template;
+            }
+            let target = 1 + distances[cell] as u32 * 3 / (max_distance as u32 + 1);
+            let fitting: Vec<&Template> = TEMPLATES
+                .iter()
+                .filter(|f| f.role == "room" && f.has_doors(&sides[cell]))
+                .collect();
+            let matching: Vec<&Template> = fitting
+                .iter()
+                .copied()
+                .filter(|f| f.difficulty == target)
+                .collect();
+            let pool = if matching.is_empty() {
+                fitting
+            } else {
+                matching
+            };
+            pool[rng.gen_range(0, pool.len())]
+        })
+        .collect();
+    let origins: Vec<(i16, i16)> = cells
+        .iter()
+        .zip(&templates)
+        .map(|(cell, template)| {
+            (
+                cell.0 * CELL_SIZE + (CELL_SIZE - template.width) / 2,
+                cell.1 * CELL_SIZE + (CELL_SIZE - template.height) / 2,
+            )
+        })
+        .collect();
+
+    let mut layers: Vec<Tiles> = vec![HashMap::new(); LAYERS.len()];
+    let mut lights = Vec::new();
+    for (cell, template) in templates.iter().enumerate() {
+        let (ox, oy) = origins[cell];
+        // rooms further from the spawn keep more of their enemies
+        let keep = 0.4 + 0.6 * distances[cell] as f32 / max_distance as f32;
+        for (layer, tiles) in template.layers.iter().enumerate() {
+            for ((x, y), tile) in tiles {
+                let mut tile = *tile;
+                // dropped spawners become wire, so the rest of their chain stays connected
+                if layer == INTERACTABLE
+                    && SPAWNER_GIDS.contains(&tile)
+                    && rng.gen_range(0.0, 1.0) > keep
+                {
+                    tile = WIRE_GID;
+                }
+                layers[layer].insert((x + ox, y + oy), tile);
+            }
+        }
+        for (pos, radius) in &template.lights {
+            lights.push((*pos + vec2(ox as f32, oy as f32) * 16.0, *radius));
+        }
+    }
+
+    let mut carved = Vec::new();
+    for (a, b, side) in &connections {
+        let door = |cell: usize, side: Side| {
+            let (ox, oy) = origins[cell];
+            templates[cell]
+                .doors
+                .iter()
+                .find(|f| f.0 == side)
+                .unwrap()
+                .1
+                .iter()
+                .map(|(x, y)| (x + ox, y + oy))
+                .collect::<Vec<_>>()
+        };
+        let door_a = door(*a, *side);
+        let door_b = door(*b, side.opposite());
+        carved.extend(&door_a);
+        carved.extend(&door_b);
+        // make sure the first door is the one to the left or top
+        let (first, second) = match side {
+            Side::East | Side::South => (door_a, door_b),
+            Side::West | Side::North => (door_b, door_a),
+        };
+        let min =
+            |door: &[(i16, i16)], f: fn(&(i16, i16)) -> i16| door.iter().map(f).min().unwrap();
+        let max =
+            |door: &[(i16, i16)], f: fn(&(i16, i16)) -> i16| door.iter().map(f).max().unwrap();
+        let x = |f: &(i16, i16)| f.0;
+        let y = |f: &(i16, i16)| f.1;
+        // a corridor bent into a Z shape, in case the doors don't line up
+        if matches!(side, Side::East | Side::West) {
+            let (start, end) = (max(&first, x) + 1, min(&second, x) - 1);
+            let middle = (start + end) / 2 - 1;
+            let (y1, y2) = (min(&first, y), min(&second, y));
+            carve(&mut carved, start, middle + 2, y1, y1 + 2);
+            carve(&mut carved, middle, middle + 2, y1.min(y2), y1.max(y2) + 2);
+            carve(&mut carved, middle, end, y2, y2 + 2);
+        } else {
+            let (start, end) = (max(&first, y) + 1, min(&second, y) - 1);
+            let middle = (start + end) / 2 - 1;
+            let (x1, x2) = (min(&first, x), min(&second, x));
+            carve(&mut carved, x1, x1 + 2, start, middle + 2);
+            carve(&mut carved, x1.min(x2), x1.max(x2) + 2, middle, middle + 2);
+            carve(&mut carved, x2, x2 + 2, middle, end);
+        }
+    }
+    for pos in &carved {
+        for (layer, tiles) in layers.iter_mut().enumerate() {
+            if layer == BACKGROUND {
+                tiles.insert(*pos, 1);
+            } else {
+                tiles.remove(pos);
+            }
+        }
+    }
+    let is_floor = |layers: &[Tiles], pos: (i16, i16)| {
+        layers[BACKGROUND].contains_key(&pos) && !layers[COLLISION].contains_key(&pos)
+    };
+    let mut walls = HashMap::new();
+    for (x, y) in &carved {
+        for dx in -1..=1 {
+            for dy in -4..=1 {
+                let pos = (x + dx, y + dy);
+                if !is_floor(&layers, pos) {
+                    let floor = |dx: i16, dy: i16| is_floor(&layers, (pos.0 + dx, pos.1 + dy));
+                    walls.insert(pos, wall_tile(floor));
+                }
+            }
+        }
+    }
+    for ((x, y), tile) in walls {
+        layers[BACKGROUND].remove(&(x, y));
+        layers[COLLISION].insert((x, y), tile);
+        // bottom walls get a ledge drawn over the floor above them
+        if tile == 50 && is_floor(&layers, (x, y - 1)) && !is_floor(&layers, (x, y + 1)) {
+            layers[DETAILS].insert((x, y - 1), 18);
+        }
+    }
+    Some(write_tmx(seed, &layers, &lights))
+}
+
+fn carve(carved: &mut Vec<(i16, i16)>, x1: i16, x2: i16, y1: i16, y2: i16) {
+    for x in x1.min(x2)..=x1.max(x2) {
+        for y in y1.min(y2)..=y1.max(y2) {
+            carved.push((x, y));
+        }
+    }
+}
+
+/// Picks the wall tile fitting for the floor around it. Walls above floor are drawn four tiles tall.
+fn wall_tile(floor: impl Fn(i16, i16) -> bool) -> i16 {
+    if floor(0, 1) {
+        50
+    } else if floor(0, 2) || floor(0, 3) {
+        34
+    } else if floor(0, 4) {
+        18
+    } else if floor(1, 0) {
+        54
+    } else if floor(-1, 0) {
+        55
+    } else if floor(0, -1) {
+        50
+    } else if floor(1, 1) || floor(1, -1) {
+        54
+    } else if floor(-1, 1) || floor(-1, -1) {
+        55
+    } else {
+        34
+    }
+}
+
+fn write_tmx(seed: u64, layers: &[Tiles], lights: &[(Vec2, f32)]) -> String {
+    let mut xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" tiledversion="1.11.2" orientation="orthogonal" renderorder="right-down" width="20" height="20" tilewidth="16" tileheight="16" infinite="1" nextlayerid="8" nextobjectid="{}">
+ <properties>
+  <property name="ambient" type="float" value="0.3"/>
+  <property name="seed" value="{seed}"/>
+ </properties>
+ <tileset firstgid="1" source="../../mail2/assets/world/tileset.tsx"/>
+"#,
+        lights.len() + 1
+    );
+    for (index, (name, tiles)) in LAYERS.iter().zip(layers).enumerate() {
+        xml += &format!(
+            " <layer id=\"{}\" name=\"{name}\" width=\"20\" height=\"20\">\n  <data encoding=\"csv\">\n",
+            index + 1
+        );
+        let mut chunks: Vec<(i16, i16)> = tiles
+            .keys()
+            .map(|(x, y)| (x.div_euclid(16) * 16, y.div_euclid(16) * 16))
+            .collect();
+        chunks.sort_by_key(|f| (f.1, f.0));
+        chunks.dedup();
+        for (cx, cy) in chunks {
+            let rows: Vec<String> = (0..16)
+                .map(|y| {
+                    (0..16)
+                        .map(|x| tiles.get(&(cx + x, cy + y)).unwrap_or(&0).to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .collect();
+            xml += &format!(
+                "   <chunk x=\"{cx}\" y=\"{cy}\" width=\"16\" height=\"16\">\n{}\n</chunk>\n",
+                rows.join(",\n")
+            );
+        }
+        xml += "  </data>\n </layer>\n";
+    }
+    xml += " <objectgroup id=\"7\" name=\"Lights\">\n";
+    for (index, (pos, radius)) in lights.iter().enumerate() {
+        xml += &format!(
+            "  <object id=\"{}\" x=\"{}\" y=\"{}\">\n   <properties>\n    <property name=\"radius\" type=\"float\" value=\"{radius}\"/>\n   </properties>\n   <point/>\n  </object>\n",
+            index + 1,
+            pos.x,
+            pos.y
+        );
+    }
+    xml += " </objectgroup>\n</map>\n";
+    xml
+}
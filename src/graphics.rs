@@ -1,17 +1,46 @@
-use crate::assets::Assets;
-use crate::player::Player;
+use crate::arena::{Arena, Highscore, WAVE_DELAY};
+use crate::assets::{Assets, World, draw_layer_tile};
+use crate::lighting::{FRAGMENT, VERTEX};
+use crate::particles::{Particles, THRUSTER_EXHAUST};
+use crate::pickups::{BUFF_DURATION, SHIELD_AMOUNT, SHIELD_COLOR};
+use crate::player::{Player, WEAPONS};
+use crate::settings::Settings;
 use crate::utils::*;
 use macroquad::miniquad::window::screen_size;
+use macroquad::miniquad::{BlendFactor, BlendState, Equation};
 use macroquad::prelude::*;
 
 pub const PLAYER_HEALTH_COLOR: Color = Color::from_hex(0x87d1ef);
+pub const SLOT_COLOR: Color = Color::from_hex(0x2b2b30);
+pub const BOSS_HEALTH_COLOR: Color = Color::from_hex(0xb0304a);
+pub const SELECTED_SLOT_COLOR: Color = Color::from_hex(0x4a5a66);
+pub const TOOLTIP_COLOR: Color = Color::from_hex(0xe76d46);
+pub const OXYGEN_COLOR: Color = Color::from_hex(0xd8f0ff);
+
+/// Turns the colours of whatever it draws around the colour wheel by the `Hue` uniform, in radians
+const PALETTE_FRAGMENT: &str = r#"#version 100
+varying lowp vec4 color;
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+uniform lowp float Hue;
+
+void main() {
+    lowp vec4 texel = texture2D(Texture, uv);
+    lowp vec3 axis = vec3(0.57735);
+    lowp float c = cos(Hue);
+    lowp vec3 rgb = texel.rgb * c + cross(axis, texel.rgb) * sin(Hue)
+        + axis * dot(axis, texel.rgb) * (1.0 - c);
+    gl_FragColor = vec4(rgb, texel.a) * color;
+}"#;
 
 pub fn draw_escape_pod(
     assets: &Assets,
     time: f32,
     player: &mut Player,
-    escape_pod: Vec2,
-    escape_pod_door: Vec2,
+    (escape_pod_door, escape_pod): (Vec2, Vec2),
+    rescued: (usize, usize),
+    particles: &mut Particles,
     delta_time: f32,
 ) {
     let walk_time = 1.5;
@@ -21,7 +50,7 @@ pub fn draw_escape_pod(
 
     if time == 0.0 {
         draw_texture_ex(
-            &assets.escape_pod.animations[0].get_at_time(0),
+            assets.escape_pod.animations[0].get_at_time(0),
             escape_pod.x,
             escape_pod.y,
             WHITE,
@@ -44,7 +73,7 @@ pub fn draw_escape_pod(
         pos = player.pos.lerp(target, time / walk_time);
 
         draw_texture_ex(
-            &assets.escape_pod.animations[0].get_at_time(0),
+            assets.escape_pod.animations[0].get_at_time(0),
             escape_pod.x,
             escape_pod.y,
             WHITE,
@@ -54,8 +83,14 @@ pub fn draw_escape_pod(
         let amt = (time - walk_time) / fly_off_time;
         let amt = 2.0_f32.powf(amt.powi(3)) - 1.0;
         let pod_pos = escape_pod.lerp(escape_pod + vec2(0.0, 1.0 * SCREEN_HEIGHT), amt);
+        particles.emit(
+            &THRUSTER_EXHAUST,
+            pod_pos + vec2(24.0, 0.0),
+            vec2(0.0, -1.0),
+            delta_time,
+        );
         draw_texture_ex(
-            &assets.escape_pod.animations[1].get_at_time((time * 1000.0) as u32),
+            assets.escape_pod.animations[1].get_at_time((time * 1000.0) as u32),
             pod_pos.x,
             pod_pos.y,
             WHITE,
@@ -81,15 +116,127 @@ pub fn draw_escape_pod(
             p.y + (SCREEN_HEIGHT - assets.win.height()) / 2.0,
             WHITE.with_alpha(amt),
         );
+        if rescued.1 > 0 {
+            let text = format!("Survivors rescued: {}/{}", rescued.0, rescued.1);
+            let size = measure_text(&text, None, 16, 1.0);
+            draw_text_ex(
+                &text,
+                (p.x + (SCREEN_WIDTH - size.width) / 2.0).floor(),
+                (p.y + (SCREEN_HEIGHT + assets.win.height()) / 2.0 + 20.0).floor(),
+                TextParams {
+                    font_size: 16,
+                    color: WHITE.with_alpha(amt),
+                    ..Default::default()
+                },
+            );
+        }
     }
     player.camera_pos = pos.floor();
 }
 
+/// Draws the world's tile layers into the cached background and foreground render targets
+pub fn draw_world(assets: &Assets, world: &World, background: &Camera2D, foreground: &Camera2D) {
+    set_camera(background);
+    clear_background(BLACK.with_alpha(0.0));
+    for chunk in &world.background {
+        chunk.draw(assets);
+    }
+    for chunk in &world.collision {
+        chunk.draw(assets);
+    }
+    for chunk in &world.background_details {
+        chunk.draw(assets);
+    }
+    set_camera(foreground);
+    clear_background(BLACK.with_alpha(0.0));
+    for chunk in &world.details {
+        chunk.draw(assets);
+    }
+}
+/// Material that replaces whatever it draws over with transparency
+pub fn erase_material() -> Option<Material> {
+    let material = load_material(
+        ShaderSource::Glsl {
+            vertex: VERTEX,
+            fragment: FRAGMENT,
+        },
+        MaterialParams {
+            pipeline_params: PipelineParams {
+                color_blend: Some(BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Zero,
+                    BlendFactor::Zero,
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+    material
+        .map_err(|err| warn!("partial world redraws disabled: {err:?}"))
+        .ok()
+}
+/// Material that swaps the palette of elite enemies
+pub fn palette_material() -> Option<Material> {
+    let material = load_material(
+        ShaderSource::Glsl {
+            vertex: VERTEX,
+            fragment: PALETTE_FRAGMENT,
+        },
+        MaterialParams {
+            uniforms: vec![UniformDesc::new("Hue", UniformType::Float1)],
+            ..Default::default()
+        },
+    );
+    material
+        .map_err(|err| warn!("elite palette swaps disabled: {err:?}"))
+        .ok()
+}
+/// Redraws the tiles broken since the last redraw in the cached render targets, or everything if
+/// they can't be erased on their own
+pub fn redraw_broken_tiles(
+    assets: &Assets,
+    world: &World,
+    background: &Camera2D,
+    foreground: &Camera2D,
+    erase: Option<&Material>,
+) {
+    let Some(erase) = erase else {
+        draw_world(assets, world, background, foreground);
+        return;
+    };
+    for (camera, layers) in [
+        (
+            background,
+            [
+                &world.background,
+                &world.collision,
+                &world.background_details,
+            ]
+            .as_slice(),
+        ),
+        (foreground, [&world.details].as_slice()),
+    ] {
+        set_camera(camera);
+        for (x, y) in &world.broken {
+            gl_use_material(erase);
+            draw_rectangle(*x as f32 * 16.0, *y as f32 * 16.0, 16.0, 16.0, WHITE);
+            gl_use_default_material();
+            for layer in layers {
+                draw_layer_tile(layer, *x, *y, assets);
+            }
+        }
+    }
+}
+
 pub fn draw_ui(
     assets: &Assets,
     player: &Player,
+    boss: Option<(&str, f32)>,
+    arena: Option<&Arena>,
     show_item_tooltip: bool,
     show_escape_tooltip: bool,
+    interact_tooltip: Option<&str>,
 ) {
     let (actual_screen_width, actual_screen_height) = screen_size();
     let scale_factor = (actual_screen_width / SCREEN_WIDTH)
@@ -127,6 +274,110 @@ pub fn draw_ui(
         },
     );
 
+    if player.shield > 0.0 {
+        draw_rectangle(
+            x + 8.0 * scale_factor,
+            y + 18.0 * scale_factor,
+            170.0 * scale_factor * player.shield / SHIELD_AMOUNT,
+            4.0 * scale_factor,
+            SHIELD_COLOR,
+        );
+    }
+    if player.oxygen < 100.0 {
+        draw_rectangle(
+            x + 8.0 * scale_factor,
+            y + 25.0 * scale_factor,
+            170.0 * scale_factor,
+            3.0 * scale_factor,
+            BLACK,
+        );
+        draw_rectangle(
+            x + 8.0 * scale_factor,
+            y + 25.0 * scale_factor,
+            170.0 * scale_factor * player.oxygen / 100.0,
+            3.0 * scale_factor,
+            OXYGEN_COLOR,
+        );
+    }
+    for (index, buff) in player.buffs.iter().enumerate() {
+        let x = x + 8.0 * scale_factor + index as f32 * 40.0 * scale_factor;
+        let y = y + 34.0 * scale_factor;
+        buff.kind
+            .draw_icon(vec2(x + 5.0 * scale_factor, y), scale_factor);
+        draw_rectangle(
+            x + 12.0 * scale_factor,
+            y - scale_factor,
+            24.0 * scale_factor,
+            3.0 * scale_factor,
+            BLACK,
+        );
+        draw_rectangle(
+            x + 12.0 * scale_factor,
+            y - scale_factor,
+            24.0 * scale_factor * buff.time / BUFF_DURATION,
+            3.0 * scale_factor,
+            WHITE,
+        );
+    }
+
+    for (index, (effect, time)) in player.statuses.effects.iter().enumerate() {
+        let index = index + player.buffs.len();
+        let x = x + 8.0 * scale_factor + index as f32 * 40.0 * scale_factor;
+        let y = y + 34.0 * scale_factor;
+        effect
+            .kind
+            .draw_icon(vec2(x + 5.0 * scale_factor, y), scale_factor);
+        draw_rectangle(
+            x + 12.0 * scale_factor,
+            y - scale_factor,
+            24.0 * scale_factor,
+            3.0 * scale_factor,
+            BLACK,
+        );
+        draw_rectangle(
+            x + 12.0 * scale_factor,
+            y - scale_factor,
+            24.0 * scale_factor * time / effect.duration,
+            3.0 * scale_factor,
+            effect.kind.color(),
+        );
+    }
+
+    for (index, keycard) in player.keycards.iter().enumerate() {
+        keycard.draw_icon(
+            vec2(
+                x + 13.0 * scale_factor + index as f32 * 14.0 * scale_factor,
+                y + 48.0 * scale_factor,
+            ),
+            scale_factor,
+        );
+    }
+
+    draw_inventory(assets, player, scale_factor);
+    if let Some((name, health)) = boss {
+        draw_boss_bar(name, health, scale_factor);
+    }
+    if let Some(arena) = arena {
+        draw_arena_counter(arena, scale_factor);
+    }
+
+    if let Some(text) = interact_tooltip
+        && !show_item_tooltip
+        && !show_escape_tooltip
+    {
+        let size = 12.0 * scale_factor;
+        let width = measure_text(text, None, size as u16, 1.0).width + 8.0 * scale_factor;
+        let height = 16.0 * scale_factor;
+        let y = actual_screen_height - height - 4.0 * scale_factor;
+        draw_rectangle(
+            (actual_screen_width - width) / 2.0,
+            y,
+            width,
+            height,
+            TOOLTIP_COLOR,
+        );
+        draw_centered_text(text, y + 12.0 * scale_factor, size, WHITE);
+    }
     let tooltip = if show_item_tooltip {
         Some(&assets.tooltip)
     } else if show_escape_tooltip {
@@ -138,7 +389,7 @@ pub fn draw_ui(
         let x = (actual_screen_width - tooltip.width() * scale_factor) / 2.0;
         let y = actual_screen_height - tooltip.height() * scale_factor - 4.0 * scale_factor;
         draw_texture_ex(
-            &tooltip,
+            tooltip,
             x,
             y,
             WHITE,
@@ -152,3 +403,224 @@ pub fn draw_ui(
         );
     }
 }
+
+fn draw_boss_bar(name: &str, health: f32, scale_factor: f32) {
+    let (actual_screen_width, _) = screen_size();
+    let width = 240.0 * scale_factor;
+    let height = 8.0 * scale_factor;
+    let x = (actual_screen_width - width) / 2.0;
+    let y = 22.0 * scale_factor;
+    let size = 12.0 * scale_factor;
+    let text_width = measure_text(name, None, size as u16, 1.0).width;
+    draw_text(
+        name,
+        (actual_screen_width - text_width) / 2.0,
+        y - 4.0 * scale_factor,
+        size,
+        WHITE,
+    );
+    draw_rectangle(
+        x - scale_factor,
+        y - scale_factor,
+        width + 2.0 * scale_factor,
+        height + 2.0 * scale_factor,
+        BLACK,
+    );
+    draw_rectangle(x, y, width, height, SLOT_COLOR);
+    draw_rectangle(x, y, width * health.max(0.0), height, BOSS_HEALTH_COLOR);
+}
+
+fn format_time(time: f32) -> String {
+    let seconds = time as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Draws text horizontally centered on the screen
+fn draw_centered_text(text: &str, y: f32, size: f32, color: Color) {
+    let (actual_screen_width, _) = screen_size();
+    let text_width = measure_text(text, None, size as u16, 1.0).width;
+    draw_text(
+        text,
+        (actual_screen_width - text_width) / 2.0,
+        y,
+        size,
+        color,
+    );
+}
+
+fn draw_arena_counter(arena: &Arena, scale_factor: f32) {
+    let (actual_screen_width, _) = screen_size();
+    let size = 12.0 * scale_factor;
+    let text = if arena.wave == 0 || arena.next_wave < WAVE_DELAY {
+        format!("WAVE {} IN {}", arena.wave + 1, arena.next_wave.ceil())
+    } else {
+        format!("WAVE {}", arena.wave)
+    };
+    for (index, text) in [text, format_time(arena.time)].iter().enumerate() {
+        let text_width = measure_text(text, None, size as u16, 1.0).width;
+        draw_text(
+            text,
+            actual_screen_width - text_width - 10.0 * scale_factor,
+            20.0 * scale_factor + index as f32 * size,
+            size,
+            WHITE,
+        );
+    }
+}
+
+fn highscore_text(highscore: Option<Highscore>) -> String {
+    match highscore {
+        Some(best) => format!("BEST: WAVE {} - {}", best.wave, format_time(best.time)),
+        None => "NO BEST RUN YET".to_string(),
+    }
+}
+
+/// Menu shown on startup to pick a mode and change settings
+pub fn draw_title(best: Option<Highscore>, settings: &Settings) {
+    let (_, actual_screen_height) = screen_size();
+    let scale_factor = (actual_screen_height / SCREEN_HEIGHT).floor().max(1.0);
+    let y = actual_screen_height / 2.0 - 60.0 * scale_factor;
+    draw_centered_text("SPACE SPLATTER", y, 24.0 * scale_factor, WHITE);
+    let size = 12.0 * scale_factor;
+    draw_centered_text("[1] STATION", y + 30.0 * scale_factor, size, WHITE);
+    draw_centered_text(
+        "[2] GENERATED STATION",
+        y + 44.0 * scale_factor,
+        size,
+        WHITE,
+    );
+    draw_centered_text("[3] ARENA", y + 58.0 * scale_factor, size, WHITE);
+    draw_centered_text(
+        &highscore_text(best),
+        y + 78.0 * scale_factor,
+        size,
+        SELECTED_SLOT_COLOR,
+    );
+
+    let on_off = |enabled: bool| if enabled { "ON" } else { "OFF" };
+    let options = [
+        format!("[4] DIFFICULTY: {}", settings.difficulty.name()),
+        format!("[5] SLOWER BULLETS: {}", on_off(settings.slower_bullets)),
+        format!(
+            "[6] DAMAGE REDUCTION: {}",
+            on_off(settings.damage_reduction)
+        ),
+        format!("[7] INVINCIBILITY: {}", on_off(settings.invincible)),
+        format!(
+            "[8] EXPORT GENERATED MAPS: {}",
+            on_off(settings.export_generated)
+        ),
+    ];
+    for (index, option) in options.iter().enumerate() {
+        draw_centered_text(
+            option,
+            y + (104.0 + index as f32 * 14.0) * scale_factor,
+            size,
+            WHITE,
+        );
+    }
+}
+
+pub fn draw_game_over(arena: &Arena) {
+    let (actual_screen_width, actual_screen_height) = screen_size();
+    let scale_factor = (actual_screen_height / SCREEN_HEIGHT).floor().max(1.0);
+    draw_rectangle(
+        0.0,
+        0.0,
+        actual_screen_width,
+        actual_screen_height,
+        BLACK.with_alpha(0.6),
+    );
+    let y = actual_screen_height / 2.0 - 20.0 * scale_factor;
+    let size = 12.0 * scale_factor;
+    draw_centered_text("GAME OVER", y, 24.0 * scale_factor, BOSS_HEALTH_COLOR);
+    draw_centered_text(
+        &format!("WAVE {} - {}", arena.wave, format_time(arena.time)),
+        y + 24.0 * scale_factor,
+        size,
+        WHITE,
+    );
+    draw_centered_text(
+        &highscore_text(arena.best),
+        y + 38.0 * scale_factor,
+        size,
+        WHITE,
+    );
+    draw_centered_text(
+        "PRESS ENTER",
+        y + 58.0 * scale_factor,
+        size,
+        SELECTED_SLOT_COLOR,
+    );
+}
+
+fn draw_inventory(assets: &Assets, player: &Player, scale_factor: f32) {
+    let (_, actual_screen_height) = screen_size();
+    let size = 22.0 * scale_factor;
+    let gap = 3.0 * scale_factor;
+    let y = actual_screen_height - size - 14.0 * scale_factor;
+    for (index, slot) in player.inventory.slots.iter().enumerate() {
+        let x = 10.0 * scale_factor + index as f32 * (size + gap);
+        let selected = index == player.inventory.selected;
+        draw_rectangle(
+            x - scale_factor,
+            y - scale_factor,
+            size + 2.0 * scale_factor,
+            size + 2.0 * scale_factor,
+            BLACK,
+        );
+        draw_rectangle(
+            x,
+            y,
+            size,
+            size,
+            if selected {
+                SELECTED_SLOT_COLOR
+            } else {
+                SLOT_COLOR
+            },
+        );
+        let icon = WEAPONS.iter().position(|f| *f == slot.weapon).unwrap() as f32;
+        draw_texture_ex(
+            &assets.tileset.texture,
+            x + 3.0 * scale_factor,
+            y + 3.0 * scale_factor,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(16.0 * scale_factor, 16.0 * scale_factor)),
+                source: Some(Rect::new(icon * 16.0, 7.0 * 16.0, 16.0, 16.0)),
+                ..Default::default()
+            },
+        );
+        if slot.reloading > 0.0 {
+            let amt = 1.0 - slot.reloading / slot.weapon.reload_time;
+            draw_rectangle(
+                x,
+                y + size - 2.0 * scale_factor,
+                size * amt,
+                2.0 * scale_factor,
+                PLAYER_HEALTH_COLOR,
+            );
+        }
+        for (i, modifier) in slot.modifiers.iter().enumerate() {
+            draw_rectangle(
+                x + size - 4.0 * scale_factor,
+                y + (1.0 + i as f32 * 3.0) * scale_factor,
+                3.0 * scale_factor,
+                2.0 * scale_factor,
+                modifier.color(),
+            );
+        }
+        let text = match slot.ammo {
+            Some(ammo) => format!("{}/{}", slot.magazine, ammo),
+            None => format!("{}", slot.magazine),
+        };
+        draw_text(
+            &text,
+            x,
+            y + size + 10.0 * scale_factor,
+            10.0 * scale_factor,
+            if selected { WHITE } else { GRAY },
+        );
+    }
+}
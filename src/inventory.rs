@@ -0,0 +1,146 @@
+use macroquad::prelude::*;
+
+use crate::{modifiers::WeaponModifier, player::Weapon};
+
+pub const MAX_SLOTS: usize = 4;
+
+pub struct WeaponSlot {
+    pub weapon: &'static Weapon,
+    pub magazine: u16,
+    /// Spare ammo, or `None` if the weapon never runs out
+    pub ammo: Option<u16>,
+    /// Time left until the current reload finishes
+    pub reloading: f32,
+    pub modifiers: Vec<WeaponModifier>,
+}
+impl WeaponSlot {
+    pub fn new(weapon: &'static Weapon) -> Self {
+        Self {
+            weapon,
+            magazine: weapon.magazine,
+            ammo: weapon.max_ammo.map(|f| f / 2),
+            reloading: 0.0,
+            modifiers: Vec::new(),
+        }
+    }
+    pub fn reload(&mut self) {
+        if self.reloading <= 0.0 && self.magazine < self.weapon.magazine && self.ammo != Some(0) {
+            self.reloading = self.weapon.reload_time;
+        }
+    }
+    pub fn add_ammo(&mut self, amount: u16) {
+        if let (Some(ammo), Some(max)) = (&mut self.ammo, self.weapon.max_ammo) {
+            *ammo = (*ammo + amount).min(max);
+        }
+    }
+}
+
+/// Something that can be taken from a locker
+#[derive(Clone, Copy)]
+pub enum LockerItem {
+    Weapon(&'static Weapon),
+    Modifier(WeaponModifier),
+}
+
+pub struct Inventory {
+    pub slots: Vec<WeaponSlot>,
+    pub selected: usize,
+}
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::with_capacity(MAX_SLOTS),
+            selected: 0,
+        }
+    }
+    pub fn current(&self) -> Option<&WeaponSlot> {
+        self.slots.get(self.selected)
+    }
+    pub fn current_mut(&mut self) -> Option<&mut WeaponSlot> {
+        self.slots.get_mut(self.selected)
+    }
+    /// Adds a weapon to the inventory. If it is already held, it is used as
+    /// ammo instead. If the inventory is full, the selected weapon is dropped
+    /// and returned.
+    pub fn add(&mut self, weapon: &'static Weapon) -> Option<&'static Weapon> {
+        if let Some(slot) = self.slots.iter_mut().find(|f| f.weapon == weapon) {
+            slot.add_ammo(weapon.magazine * 2);
+            return None;
+        }
+        if self.slots.len() < MAX_SLOTS {
+            self.slots.push(WeaponSlot::new(weapon));
+            self.selected = self.slots.len() - 1;
+            return None;
+        }
+        let old = std::mem::replace(&mut self.slots[self.selected], WeaponSlot::new(weapon));
+        Some(old.weapon)
+    }
+    /// Attaches a modifier to the selected weapon. Returns whether there was a weapon to attach it to.
+    pub fn add_modifier(&mut self, modifier: WeaponModifier) -> bool {
+        let Some(slot) = self.current_mut() else {
+            return false;
+        };
+        slot.modifiers.push(modifier);
+        true
+    }
+    /// Refills every held weapon with a magazine worth of spare ammo
+    pub fn add_ammo(&mut self) {
+        for slot in self.slots.iter_mut() {
+            slot.add_ammo(slot.weapon.magazine);
+        }
+    }
+    pub fn select(&mut self, index: usize) {
+        if index < self.slots.len() && index != self.selected {
+            if let Some(slot) = self.current_mut() {
+                slot.reloading = 0.0;
+            }
+            self.selected = index;
+        }
+    }
+    pub fn update(&mut self, delta_time: f32) {
+        let scroll = mouse_wheel().1;
+        if scroll != 0.0 && !self.slots.is_empty() {
+            let amount = if scroll > 0.0 {
+                self.slots.len() - 1
+            } else {
+                1
+            };
+            self.select((self.selected + amount) % self.slots.len());
+        }
+        for (index, key) in [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4]
+            .into_iter()
+            .enumerate()
+        {
+            if is_key_pressed(key) {
+                self.select(index);
+            }
+        }
+        let Some(slot) = self.current_mut() else {
+            return;
+        };
+        if is_key_pressed(KeyCode::R) || slot.magazine == 0 {
+            slot.reload();
+        }
+        if slot.reloading > 0.0 {
+            slot.reloading -= delta_time;
+            if slot.reloading <= 0.0 {
+                let wanted = slot.weapon.magazine - slot.magazine;
+                let amount = slot.ammo.map_or(wanted, |f| f.min(wanted));
+                if let Some(ammo) = &mut slot.ammo {
+                    *ammo -= amount;
+                }
+                slot.magazine += amount;
+                slot.reloading = 0.0;
+            }
+        }
+    }
+    /// Uses up a round of the selected weapon, returning it if it can fire
+    pub fn fire(&mut self) -> Option<&'static Weapon> {
+        let slot = self.current_mut()?;
+        if slot.reloading > 0.0 || slot.magazine == 0 {
+            return None;
+        }
+        slot.magazine -= 1;
+        Some(slot.weapon)
+    }
+}
@@ -0,0 +1,255 @@
+use std::f32::consts::PI;
+
+use macroquad::{
+    miniquad::{BlendFactor, BlendState, BlendValue, Equation},
+    prelude::*,
+};
+
+use crate::{
+    assets::World,
+    player::{Player, Projectile},
+    tile_entities::FIRE,
+    utils::*,
+};
+
+#[cfg(target_arch = "wasm32")]
+const RAY_COUNT: usize = 48;
+#[cfg(not(target_arch = "wasm32"))]
+const RAY_COUNT: usize = 96;
+
+const FLASHLIGHT_RANGE: f32 = 150.0;
+const FLASHLIGHT_WIDTH: f32 = PI / 5.0;
+const FLASHLIGHT_COLOR: Color = Color::new(1.0, 0.95, 0.8, 1.0);
+const PLAYER_GLOW_COLOR: Color = Color::new(0.35, 0.35, 0.4, 1.0);
+/// Starlight coming in through open hull breaches
+const BREACH_LIGHT_COLOR: Color = Color::new(0.3, 0.4, 0.55, 1.0);
+const FIRE_LIGHT_COLOR: Color = Color::new(0.6, 0.3, 0.1, 1.0);
+
+pub const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}"#;
+pub const FRAGMENT: &str = r#"#version 100
+varying lowp vec4 color;
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+
+void main() {
+    gl_FragColor = color * texture2D(Texture, uv);
+}"#;
+
+pub struct Light {
+    pub pos: Vec2,
+    pub radius: f32,
+    pub color: Color,
+    /// Direction and total width of the light, if it is a cone
+    pub cone: Option<(f32, f32)>,
+    /// Visible outline of the light, or `None` if it shines through walls
+    pub polygon: Option<Vec<Vec2>>,
+}
+impl Light {
+    pub fn new(pos: Vec2, radius: f32, color: Color) -> Self {
+        Self {
+            pos,
+            radius,
+            color,
+            cone: None,
+            polygon: None,
+        }
+    }
+    /// Traces the outline of the light against the collision layer
+    pub fn cast_shadows(&mut self, world: &World) {
+        let (start, width) = match self.cone {
+            Some((angle, width)) => (angle - width / 2.0, width),
+            None => (0.0, 2.0 * PI),
+        };
+        let rays = (RAY_COUNT as f32 * width / (2.0 * PI)).ceil().max(8.0) as usize;
+        self.polygon = Some(
+            (0..=rays)
+                .map(|i| {
+                    let dir = Vec2::from_angle(start + width * i as f32 / rays as f32);
+                    self.pos + dir * world.raycast(self.pos, dir, self.radius)
+                })
+                .collect(),
+        );
+    }
+    fn draw(&self) {
+        let edge = |point: Vec2| {
+            let amt = 1.0 - point.distance(self.pos) / self.radius;
+            Color::new(
+                self.color.r * amt,
+                self.color.g * amt,
+                self.color.b * amt,
+                1.0,
+            )
+        };
+        let mut vertices = vec![Vertex::new(
+            self.pos.x, self.pos.y, 0.0, 0.0, 0.0, self.color,
+        )];
+        match &self.polygon {
+            Some(polygon) => {
+                vertices.extend(
+                    polygon
+                        .iter()
+                        .map(|p| Vertex::new(p.x, p.y, 0.0, 0.0, 0.0, edge(*p))),
+                );
+            }
+            None => {
+                let (start, width) = self.cone.map_or((0.0, 2.0 * PI), |(a, w)| (a - w / 2.0, w));
+                let rays = 16;
+                vertices.extend((0..=rays).map(|i| {
+                    let p = self.pos
+                        + Vec2::from_angle(start + width * i as f32 / rays as f32) * self.radius;
+                    Vertex::new(p.x, p.y, 0.0, 0.0, 0.0, BLACK)
+                }));
+            }
+        }
+        let mut indices = Vec::with_capacity(vertices.len() * 3);
+        for i in 1..vertices.len() as u16 - 1 {
+            indices.extend_from_slice(&[0, i, i + 1]);
+        }
+        draw_mesh(&Mesh {
+            vertices,
+            indices,
+            texture: None,
+        });
+    }
+    fn on_screen(&self, center: Vec2) -> bool {
+        (self.pos.x - center.x).abs() < SCREEN_WIDTH / 2.0 + self.radius
+            && (self.pos.y - center.y).abs() < SCREEN_HEIGHT / 2.0 + self.radius
+    }
+}
+
+/// Renders a light map each frame and multiplies it over the pixel camera.
+///
+/// If the blending materials can't be created (such as on limited WebGL
+/// contexts), lights are skipped and the scene is just dimmed by the ambient
+/// level instead.
+pub struct Lighting {
+    light_camera: Camera2D,
+    materials: Option<(Material, Material)>,
+}
+impl Lighting {
+    pub fn new() -> Self {
+        let material = |blend| {
+            load_material(
+                ShaderSource::Glsl {
+                    vertex: VERTEX,
+                    fragment: FRAGMENT,
+                },
+                MaterialParams {
+                    pipeline_params: PipelineParams {
+                        color_blend: Some(blend),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+        };
+        let additive = material(BlendState::new(
+            Equation::Add,
+            BlendFactor::One,
+            BlendFactor::One,
+        ));
+        let multiply = material(BlendState::new(
+            Equation::Add,
+            BlendFactor::Zero,
+            BlendFactor::Value(BlendValue::SourceColor),
+        ));
+        let materials = match (additive, multiply) {
+            (Ok(additive), Ok(multiply)) => Some((additive, multiply)),
+            (Err(err), _) | (_, Err(err)) => {
+                warn!("lighting disabled: {err:?}");
+                None
+            }
+        };
+        Self {
+            light_camera: create_camera(SCREEN_WIDTH, SCREEN_HEIGHT),
+            materials,
+        }
+    }
+    /// Draws the lighting over everything drawn so far with `pixel_camera`,
+    /// and leaves `pixel_camera` active afterwards
+    pub fn draw(
+        &mut self,
+        pixel_camera: &Camera2D,
+        world: &World,
+        player: &Player,
+        projectiles: &[Projectile],
+        mouse: (f32, f32),
+        flashlight: bool,
+    ) {
+        let center = pixel_camera.target;
+        let Some((additive, multiply)) = &self.materials else {
+            draw_rectangle(
+                center.x - SCREEN_WIDTH / 2.0,
+                center.y - SCREEN_HEIGHT / 2.0,
+                SCREEN_WIDTH,
+                SCREEN_HEIGHT,
+                BLACK.with_alpha((1.0 - world.ambient) * 0.7),
+            );
+            return;
+        };
+        if world.ambient >= 1.0 {
+            return;
+        }
+
+        self.light_camera.target = center;
+        set_camera(&self.light_camera);
+        clear_background(Color::new(world.ambient, world.ambient, world.ambient, 1.0));
+        gl_use_material(additive);
+        for light in world.lights.iter().filter(|f| f.on_screen(center)) {
+            light.draw();
+        }
+        for breach in world.open_breaches() {
+            Light::new(breach, 32.0, BREACH_LIGHT_COLOR).draw();
+        }
+        for ((x, y), fire) in world
+            .tile_entities
+            .iter()
+            .filter(|(_, f)| std::ptr::eq(f.kind, &FIRE))
+        {
+            let radius = 36.0 + 4.0 * (fire.timer * 7.0).sin();
+            let pos = vec2(*x as f32, *y as f32) * 16.0 + 8.0;
+            Light::new(pos, radius, FIRE_LIGHT_COLOR).draw();
+        }
+        for projectile in projectiles {
+            if let Some((radius, color)) = projectile.ty.light {
+                Light::new(projectile.pos, radius, color).draw();
+            }
+        }
+        if flashlight {
+            let origin = player.pos + 8.0;
+            Light::new(origin, 40.0, PLAYER_GLOW_COLOR).draw();
+            let aim = vec2(mouse.0, mouse.1) - vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+            let mut cone = Light::new(origin, FLASHLIGHT_RANGE, FLASHLIGHT_COLOR);
+            cone.cone = Some((aim.to_angle(), FLASHLIGHT_WIDTH));
+            cone.cast_shadows(world);
+            cone.draw();
+        }
+        gl_use_default_material();
+
+        set_camera(pixel_camera);
+        gl_use_material(multiply);
+        draw_texture(
+            &self.light_camera.render_target.as_ref().unwrap().texture,
+            center.x - SCREEN_WIDTH / 2.0,
+            center.y - SCREEN_HEIGHT / 2.0,
+            WHITE,
+        );
+        gl_use_default_material();
+    }
+}
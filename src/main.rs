@@ -1,13 +1,53 @@
+use std::sync::LazyLock;
+
 use macroquad::{miniquad::window::screen_size, prelude::*};
 
-use crate::{assets::*, enemy::*, player::*, utils::*};
+use crate::{
+    arena::{Arena, Highscore},
+    assets::*,
+    boss::{BOSS_INTRO_TIME, BOSSES},
+    companions::{CompanionKind, SURVIVOR_BOARD_RANGE, SURVIVOR_REWARD},
+    enemy::*,
+    inventory::LockerItem,
+    lighting::*,
+    particles::*,
+    patterns::{PATTERNS, PatternRunner},
+    pickups::Pickup,
+    player::*,
+    settings::{Difficulty, set_settings, settings},
+    tile_entities::{TileEntityContext, update_tile_entities},
+    utils::*,
+};
 
+mod arena;
 mod assets;
+mod boss;
+mod companions;
+mod elites;
 mod enemy;
+mod entities;
+mod generator;
 mod graphics;
+mod inventory;
+mod lighting;
+mod modifiers;
+mod particles;
+mod patterns;
+mod pickups;
 mod player;
+mod rooms;
+mod settings;
+mod status;
+mod tile_entities;
 mod utils;
 
+enum Mode {
+    Station,
+    /// A station put together by the generator from the given seed
+    Generated(u64),
+    Arena,
+}
+
 struct Game<'a> {
     assets: &'a Assets,
     world: World,
@@ -15,16 +55,41 @@ struct Game<'a> {
     pixel_camera: Camera2D,
     world_camera_bg: Camera2D,
     world_camera_fg: Camera2D,
+    /// Erases broken tiles from the world render targets, if it could be created
+    erase_material: Option<Material>,
     stars: StarsBackground,
     enemies: Vec<Enemy>,
     projectiles: Vec<Projectile>,
-    escape_pod_door: Vec2,
-    escape_pod: Vec2,
+    particles: Particles,
+    patterns: Vec<PatternRunner>,
+    lighting: Lighting,
+    /// Door and body of the escape pod, which the arena doesn't have
+    escape_pod: Option<(Vec2, Vec2)>,
     escaping_animation: f32,
+    /// Survivors who boarded the escape pod, out of how many the map has
+    rescued: (usize, usize),
+    /// Position of the boss being introduced and how long the camera has been panning to it
+    boss_intro: Option<(Vec2, f32)>,
+    arena: Option<Arena>,
 }
 impl<'a> Game<'a> {
-    fn new(assets: &'a Assets) -> Self {
-        let world = World::default();
+    fn new(assets: &'a Assets, mode: Mode) -> Self {
+        let world = match mode {
+            Mode::Station => World::default(),
+            Mode::Generated(seed) => match generator::generate(seed) {
+                Ok(station) => {
+                    if settings().export_generated {
+                        generator::export(&station);
+                    }
+                    World::new(&station)
+                }
+                Err(err) => {
+                    warn!("{err}, loading the station instead");
+                    World::default()
+                }
+            },
+            Mode::Arena => World::new(include_str!("../assets/arena.tmx")),
+        };
 
         let world_width = ((world.x_max - world.x_min) * 16) as f32 + 16.0 * 16.0;
         let world_height = ((world.y_max - world.y_min) * 16) as f32 + 16.0 * 16.0;
@@ -35,50 +100,46 @@ impl<'a> Game<'a> {
             (world.x_min + world.x_max + 16) as f32 / 2.0 * 16.0,
             (world.y_min + world.y_max + 16) as f32 / 2.0 * 16.0,
         );
-        set_camera(&world_camera_bg);
-        clear_background(BLACK.with_alpha(0.0));
-
-        for chunk in &world.background {
-            chunk.draw(assets);
-        }
-        for chunk in &world.collision {
-            chunk.draw(assets);
-        }
-        for chunk in &world.background_details {
-            chunk.draw(assets);
-        }
         let mut world_camera_fg = create_camera(world_width, world_height);
-        world_camera_fg.target = vec2(
-            (world.x_min + world.x_max + 16) as f32 / 2.0 * 16.0,
-            (world.y_min + world.y_max + 16) as f32 / 2.0 * 16.0,
-        );
-        set_camera(&world_camera_fg);
-        clear_background(BLACK.with_alpha(0.0));
-        for chunk in &world.details {
-            chunk.draw(assets);
-        }
+        world_camera_fg.target = world_camera_bg.target;
+        graphics::draw_world(assets, &world, &world_camera_bg, &world_camera_fg);
 
         let pixel_camera = create_camera(SCREEN_WIDTH, SCREEN_HEIGHT);
 
         let mut player = Player::new();
         player.pos = world.get_interactable_spawn(16).unwrap();
+        let survivors = world
+            .companions
+            .iter()
+            .filter(|f| f.kind == CompanionKind::Survivor)
+            .count();
 
         Self {
-            escape_pod_door: world.get_interactable_spawn(128).unwrap() + vec2(0.0, 8.0),
-            escape_pod: world.get_interactable_spawn(129).unwrap(),
+            escape_pod: world
+                .get_interactable_spawn(128)
+                .zip(world.get_interactable_spawn(129))
+                .map(|(door, pod)| (door + vec2(0.0, 8.0), pod)),
+            arena: matches!(mode, Mode::Arena).then(|| Arena::new(&world)),
             player,
             assets,
             world,
             pixel_camera,
             world_camera_bg,
             world_camera_fg,
+            erase_material: graphics::erase_material(),
             enemies: Vec::with_capacity(10), // todo: adjust capcacity later on?
             stars: StarsBackground::new(),
             projectiles: Vec::with_capacity(10),
+            particles: Particles::new(),
+            patterns: Vec::new(),
+            lighting: Lighting::new(),
             escaping_animation: 0.0,
+            rescued: (0, survivors),
+            boss_intro: None,
         }
     }
-    fn update(&mut self) {
+    /// Returns false once the game is over and the player wants to go back to the menu
+    fn update(&mut self) -> bool {
         // cap delta time to a minimum of 60 fps.
         let delta_time = get_frame_time().min(1.0 / 60.0);
         let (actual_screen_width, actual_screen_height) = screen_size();
@@ -88,17 +149,62 @@ impl<'a> Game<'a> {
         let mouse_x = mouse_x / scale_factor;
         let mouse_y = mouse_y / scale_factor;
 
-        if self.escaping_animation == 0.0 {
+        if let Some((pos, time)) = &mut self.boss_intro {
+            *time += delta_time;
+            let pan = (*time / 0.5)
+                .min((BOSS_INTRO_TIME - *time) / 0.5)
+                .clamp(0.0, 1.0);
+            let pan = pan * pan * (3.0 - 2.0 * pan);
+            self.player.camera_pos = self.player.pos.lerp(*pos - 8.0, pan);
+            if *time >= BOSS_INTRO_TIME {
+                self.boss_intro = None;
+            }
+        } else if self.arena.as_ref().is_some_and(|f| f.over) {
+            // the player stays down once an arena run is over
+        } else if self.escaping_animation == 0.0 {
             self.player.update(
                 delta_time,
-                &mut self.world,
+                &self.world,
                 &mut self.enemies,
                 &mut self.projectiles,
+                &mut self.particles,
                 (mouse_x, mouse_y),
             );
+            if let Some(boss) = self
+                .enemies
+                .iter()
+                .find(|f| f.boss.is_some() && f.emerging && f.animation_time == 0.0)
+            {
+                self.boss_intro = Some((boss.pos, 0.0));
+            }
+            let mut objects = std::mem::take(&mut self.world.objects);
+            objects.update(&self.player, &mut self.world, &mut self.enemies);
+            self.world.objects = objects;
+            let mut rooms = std::mem::take(&mut self.world.rooms);
+            for room in rooms.iter_mut() {
+                room.update(delta_time, &self.player, &mut self.world, &mut self.enemies);
+            }
+            self.world.rooms = rooms;
+            if let Some(arena) = &mut self.arena {
+                arena.update(delta_time, &self.player, &mut self.world, &mut self.enemies);
+            }
         } else {
             self.escaping_animation += delta_time;
         }
+        if !self.world.broken.is_empty() {
+            graphics::redraw_broken_tiles(
+                self.assets,
+                &self.world,
+                &self.world_camera_bg,
+                &self.world_camera_fg,
+                self.erase_material.as_ref(),
+            );
+            self.world.broken.clear();
+            // paths may lead through the new gaps now
+            for enemy in self.enemies.iter_mut() {
+                enemy.path = None;
+            }
+        }
         self.pixel_camera.target = self.player.camera_pos.floor();
         set_camera(&self.pixel_camera);
         clear_background(BLACK);
@@ -113,32 +219,51 @@ impl<'a> Game<'a> {
             DrawTextureParams::default(),
         );
         let mut can_take_weapon = false;
+        // one press of the interact key only does one thing, taken by whatever gets to it first
+        let mut interact = is_key_pressed(KeyCode::E);
 
         for (locker_pos, slot) in self.world.lockers.iter_mut() {
             if (self.player.pos + vec2(-8.0, 8.0)).distance_squared(*locker_pos) < 512.0 {
                 draw_texture_ex(
-                    &self.assets.locker.get_at_time(1),
+                    self.assets.locker.get_at_time(1),
                     locker_pos.x,
                     locker_pos.y - 48.0 + 16.0,
                     WHITE,
                     DrawTextureParams::default(),
                 );
-                if let Some(weapon) = slot {
+                if let Some(item) = *slot {
                     can_take_weapon = true;
-                    self.assets.tileset.draw_tile(
-                        locker_pos.x + 8.0,
-                        locker_pos.y - 8.0,
-                        WEAPONS.iter().position(|f| f == weapon).unwrap() as f32,
-                        7.0,
-                        None,
-                    );
-                    if is_key_pressed(KeyCode::E) {
-                        std::mem::swap(&mut self.player.weapon, slot);
+                    match item {
+                        LockerItem::Weapon(weapon) => self.assets.tileset.draw_tile(
+                            locker_pos.x + 8.0,
+                            locker_pos.y - 8.0,
+                            WEAPONS.iter().position(|f| *f == weapon).unwrap() as f32,
+                            7.0,
+                            None,
+                        ),
+                        LockerItem::Modifier(modifier) => {
+                            modifier.draw_icon(*locker_pos + vec2(16.0, 0.0), 1.0)
+                        }
+                    }
+                    if interact {
+                        interact = false;
+                        *slot = match item {
+                            LockerItem::Weapon(weapon) => {
+                                self.player.inventory.add(weapon).map(LockerItem::Weapon)
+                            }
+                            LockerItem::Modifier(modifier) => {
+                                if self.player.inventory.add_modifier(modifier) {
+                                    None
+                                } else {
+                                    Some(item)
+                                }
+                            }
+                        };
                     }
                 }
             } else {
                 draw_texture_ex(
-                    &self.assets.locker.get_at_time(0),
+                    self.assets.locker.get_at_time(0),
                     locker_pos.x,
                     locker_pos.y - 48.0 + 16.0,
                     WHITE,
@@ -147,34 +272,130 @@ impl<'a> Game<'a> {
             }
         }
 
-        for ((x, y), entity) in self.world.tile_entities.iter_mut() {
+        let mut pickups = std::mem::take(&mut self.world.pickups);
+        pickups.retain_mut(|pickup| pickup.update(&mut self.player, &self.world, delta_time));
+        self.world.pickups = pickups;
+        for pickup in &self.world.pickups {
+            pickup.draw();
+        }
+        let mut tile_entities = std::mem::take(&mut self.world.tile_entities);
+        let blocking = tile_entities
+            .iter()
+            .filter(|(_, f)| f.collision && f.enabled)
+            .map(|f| *f.0)
+            .collect();
+        let interact_tooltip = update_tile_entities(
+            &mut tile_entities,
+            &mut TileEntityContext {
+                delta_time,
+                player: &mut self.player,
+                enemies: &mut self.enemies,
+                projectiles: &mut self.projectiles,
+                particles: &mut self.particles,
+                world: &self.world,
+                blocking: &blocking,
+            },
+            interact,
+        );
+        interact &= interact_tooltip.is_none();
+        self.world.tile_entities = tile_entities;
+        for ((x, y), entity) in self.world.tile_entities.iter() {
             let pos = vec2(*x as f32, *y as f32) * 16.0;
-            (entity.draw)(entity, self.assets, pos);
+            (entity.kind.draw)(entity, self.assets, pos);
         }
         if self.escaping_animation == 0.0 {
             self.player.draw(self.assets, (mouse_x, mouse_y));
         }
+        let mut companions = std::mem::take(&mut self.world.companions);
+        for companion in companions.iter_mut() {
+            if self.escaping_animation == 0.0 {
+                companion.update(
+                    delta_time,
+                    &self.player,
+                    &self.enemies,
+                    &self.world,
+                    &mut self.projectiles,
+                    &mut self.particles,
+                );
+            }
+            companion.draw(self.assets);
+        }
+        let mut drops = Vec::new();
+        let mut hazards = Vec::new();
+        let mut splits = Vec::new();
+        let mut opened = Vec::new();
         self.enemies.retain_mut(|enemy| {
             enemy.update(
                 delta_time,
                 &mut self.player,
+                &mut companions,
                 &self.world,
                 self.assets,
                 &mut self.projectiles,
+                &mut self.particles,
             );
             enemy.draw(self.assets);
+            if enemy.health <= 0.0 {
+                drops.append(&mut enemy.drop_loot());
+                if let Some(hazard) = enemy.death_hazard() {
+                    hazards.push((hazard, enemy.pos));
+                }
+                splits.append(&mut enemy.elite_death(&mut self.projectiles));
+                if let Some(arena) = enemy.boss.as_mut().and_then(|f| f.arena.take()) {
+                    opened.push(arena);
+                }
+            }
             enemy.health > 0.0
         });
+        self.world.pickups.append(&mut drops);
+        self.enemies.append(&mut splits);
+        for (hazard, pos) in hazards {
+            if !self.world.is_solid(pos) {
+                self.world
+                    .tile_entities
+                    .entry(vec2_to_tile(pos))
+                    .or_insert_with(|| hazard.instantiate(0));
+            }
+        }
+        // removing the whole wiring also keeps the arena from closing again on the way back
+        self.world
+            .tile_entities
+            .retain(|_, f| f.id.is_none_or(|f| !opened.contains(&f)));
 
         self.projectiles.retain_mut(|projectile| {
-            projectile.update(
+            let alive = projectile.update(
                 self.assets,
                 &mut self.enemies,
                 &mut self.player,
-                &self.world,
+                &mut companions,
+                &mut self.world,
+                &mut self.particles,
+                delta_time,
+            );
+            if !alive && let Some(pattern) = projectile.spawns {
+                self.patterns
+                    .push(PatternRunner::new(pattern, projectile.pos, projectile.dir));
+            }
+            alive
+        });
+        companions.retain(|companion| {
+            if companion.health <= 0.0 {
+                self.particles
+                    .emit(&EXPLOSION, companion.pos + 8.0, Vec2::ZERO, delta_time);
+            }
+            companion.health > 0.0
+        });
+        self.world.companions = companions;
+        self.patterns.retain_mut(|pattern| {
+            pattern.update(
                 delta_time,
+                self.player.pos + 8.0,
+                None,
+                &mut self.projectiles,
             )
         });
+        self.particles.update(delta_time, &self.world);
+        self.particles.draw(self.assets);
         draw_texture_ex(
             &self.world_camera_fg.render_target.as_ref().unwrap().texture,
             (self.world.x_min * 16) as f32,
@@ -182,14 +403,25 @@ impl<'a> Game<'a> {
             WHITE,
             DrawTextureParams::default(),
         );
-        graphics::draw_escape_pod(
-            self.assets,
-            self.escaping_animation,
-            &mut self.player,
-            self.escape_pod,
-            self.escape_pod_door,
-            delta_time,
+        self.lighting.draw(
+            &self.pixel_camera,
+            &self.world,
+            &self.player,
+            &self.projectiles,
+            (mouse_x, mouse_y),
+            self.escaping_animation == 0.0,
         );
+        if let Some(escape_pod) = self.escape_pod {
+            graphics::draw_escape_pod(
+                self.assets,
+                self.escaping_animation,
+                &mut self.player,
+                escape_pod,
+                self.rescued,
+                &mut self.particles,
+                delta_time,
+            );
+        }
         set_default_camera();
         clear_background(BLACK);
         draw_texture_ex(
@@ -205,22 +437,94 @@ impl<'a> Game<'a> {
                 ..Default::default()
             },
         );
+        if let Some((door, _)) = self.escape_pod {
+            let mut boarded = 0;
+            self.world.companions.retain(|f| {
+                let boarding = f.kind == CompanionKind::Survivor
+                    && f.following
+                    && (f.pos + 8.0).distance(door) < SURVIVOR_BOARD_RANGE;
+                boarded += boarding as usize;
+                !boarding
+            });
+            self.rescued.0 += boarded;
+            for _ in 0..boarded {
+                self.world.pickups.push(Pickup::new(SURVIVOR_REWARD, door));
+            }
+        }
         let by_escape_pod = self.escaping_animation == 0.0
-            && self.player.pos.distance_squared(self.escape_pod_door) < 256.0;
-        if by_escape_pod && is_key_pressed(KeyCode::E) {
+            && self
+                .escape_pod
+                .is_some_and(|(door, _)| self.player.pos.distance_squared(door) < 256.0);
+        if by_escape_pod && interact {
             self.escaping_animation += 0.001;
         }
         if self.escaping_animation == 0.0 {
-            graphics::draw_ui(self.assets, &self.player, can_take_weapon, by_escape_pod);
+            let boss = self.enemies.iter().find_map(|enemy| {
+                let boss = enemy.boss.as_ref()?;
+                Some((
+                    boss.ty.name,
+                    enemy.health / settings().enemy_health(boss.ty.health),
+                ))
+            });
+            graphics::draw_ui(
+                self.assets,
+                &self.player,
+                boss,
+                self.arena.as_ref(),
+                can_take_weapon,
+                by_escape_pod,
+                interact_tooltip,
+            );
         }
+        if let Some(arena) = &self.arena
+            && arena.over
+        {
+            graphics::draw_game_over(arena);
+            return !is_key_pressed(KeyCode::Enter);
+        }
+        true
     }
 }
 #[macroquad::main("space splatter")]
 async fn main() {
     let assets = Assets::default();
-    let mut game = Game::new(&assets);
+    // build the enemy tables and parse the bullet patterns now, so mistakes in them show up on
+    // startup rather than mid game
+    LazyLock::force(&ENEMIES);
+    LazyLock::force(&BOSSES);
+    LazyLock::force(&PATTERNS);
     loop {
-        game.update();
-        next_frame().await
+        let best = Highscore::load();
+        let mode = loop {
+            let mut current = settings();
+            if is_key_pressed(KeyCode::Key4) {
+                let index = Difficulty::ALL
+                    .iter()
+                    .position(|f| *f == current.difficulty)
+                    .unwrap();
+                current.difficulty = Difficulty::ALL[(index + 1) % Difficulty::ALL.len()];
+            }
+            current.slower_bullets ^= is_key_pressed(KeyCode::Key5);
+            current.damage_reduction ^= is_key_pressed(KeyCode::Key6);
+            current.invincible ^= is_key_pressed(KeyCode::Key7);
+            current.export_generated ^= is_key_pressed(KeyCode::Key8);
+            set_settings(current);
+            clear_background(BLACK);
+            graphics::draw_title(best, &current);
+            if is_key_pressed(KeyCode::Key1) {
+                break Mode::Station;
+            }
+            if is_key_pressed(KeyCode::Key2) {
+                break Mode::Generated(miniquad::date::now() as u64);
+            }
+            if is_key_pressed(KeyCode::Key3) {
+                break Mode::Arena;
+            }
+            next_frame().await
+        };
+        let mut game = Game::new(&assets, mode);
+        while game.update() {
+            next_frame().await
+        }
     }
 }
@@ -0,0 +1,132 @@
+use macroquad::prelude::*;
+
+use crate::player::{HomingTarget, ProjectileBehaviour};
+
+/// Upgrades that can be attached to a held weapon. Each copy stacks.
+#[derive(Clone, Copy, PartialEq)]
+pub enum WeaponModifier {
+    Piercing,
+    Bouncing,
+    Homing,
+    Explosive,
+    Focused,
+    ExtraProjectile,
+}
+impl WeaponModifier {
+    /// Modifier lockers in the "Interactable" layer come after the weapon lockers, at tile 115
+    pub fn from_tile(tile: i16) -> Option<Self> {
+        Some(match tile {
+            115 => Self::Piercing,
+            116 => Self::Bouncing,
+            117 => Self::Homing,
+            118 => Self::Explosive,
+            119 => Self::Focused,
+            120 => Self::ExtraProjectile,
+            _ => return None,
+        })
+    }
+    pub fn color(&self) -> Color {
+        match self {
+            WeaponModifier::Piercing => Color::from_hex(0xd0d0e0),
+            WeaponModifier::Bouncing => Color::from_hex(0x60e0a0),
+            WeaponModifier::Homing => Color::from_hex(0xe060d0),
+            WeaponModifier::Explosive => Color::from_hex(0xf06030),
+            WeaponModifier::Focused => Color::from_hex(0x60c0f0),
+            WeaponModifier::ExtraProjectile => Color::from_hex(0xf0e060),
+        }
+    }
+    /// Draws the icon of this modifier, centered on `pos`, with each pixel `scale` wide
+    pub fn draw_icon(&self, pos: Vec2, scale: f32) {
+        let color = self.color();
+        let rect = |x: f32, y: f32, w: f32, h: f32| {
+            draw_rectangle(
+                pos.x + x * scale,
+                pos.y + y * scale,
+                w * scale,
+                h * scale,
+                color,
+            );
+        };
+        draw_rectangle(
+            pos.x - 5.0 * scale,
+            pos.y - 5.0 * scale,
+            10.0 * scale,
+            10.0 * scale,
+            BLACK,
+        );
+        match self {
+            WeaponModifier::Piercing => {
+                rect(-4.0, -1.0, 6.0, 2.0);
+                rect(2.0, -3.0, 1.0, 6.0);
+                rect(3.0, -2.0, 1.0, 4.0);
+            }
+            WeaponModifier::Bouncing => {
+                rect(-4.0, -4.0, 2.0, 2.0);
+                rect(-2.0, -2.0, 2.0, 2.0);
+                rect(0.0, 0.0, 2.0, 2.0);
+                rect(2.0, -2.0, 2.0, 2.0);
+                rect(-4.0, 2.0, 8.0, 2.0);
+            }
+            WeaponModifier::Homing => {
+                rect(-4.0, -1.0, 8.0, 2.0);
+                rect(-1.0, -4.0, 2.0, 8.0);
+                rect(-3.0, -3.0, 1.0, 1.0);
+                rect(2.0, 2.0, 1.0, 1.0);
+            }
+            WeaponModifier::Explosive => {
+                rect(-2.0, -4.0, 4.0, 8.0);
+                rect(-4.0, -2.0, 8.0, 4.0);
+            }
+            WeaponModifier::Focused => {
+                rect(-4.0, -4.0, 2.0, 8.0);
+                rect(2.0, -4.0, 2.0, 8.0);
+                rect(-1.0, -2.0, 2.0, 4.0);
+            }
+            WeaponModifier::ExtraProjectile => {
+                rect(-4.0, -3.0, 3.0, 2.0);
+                rect(1.0, -3.0, 3.0, 2.0);
+                rect(-2.0, 1.0, 4.0, 2.0);
+            }
+        }
+    }
+}
+
+/// Behaviour of a single projectile, starting from its type and added onto by the
+/// modifiers on the weapon that fired it
+#[derive(Clone, Copy)]
+pub struct ProjectileModifiers {
+    /// Amount of enemies the projectile can still pass through
+    pub pierce: u8,
+    /// Amount of times the projectile can still bounce off walls
+    pub bounces: u8,
+    pub homing: f32,
+    pub target: HomingTarget,
+    pub explosion_radius: f32,
+}
+impl ProjectileModifiers {
+    pub fn from_behaviour(behaviour: &ProjectileBehaviour) -> Self {
+        Self {
+            pierce: behaviour.pierce,
+            bounces: behaviour.bounces,
+            homing: behaviour.homing,
+            target: behaviour.target,
+            explosion_radius: behaviour.explosion_radius,
+        }
+    }
+    pub fn apply(&mut self, modifiers: &[WeaponModifier]) {
+        for modifier in modifiers {
+            match modifier {
+                WeaponModifier::Piercing => self.pierce += 1,
+                WeaponModifier::Bouncing => self.bounces += 2,
+                WeaponModifier::Homing => {
+                    if self.homing == 0.0 {
+                        self.target = HomingTarget::Forward;
+                    }
+                    self.homing += 4.0;
+                }
+                WeaponModifier::Explosive => self.explosion_radius += 24.0,
+                WeaponModifier::Focused | WeaponModifier::ExtraProjectile => {}
+            }
+        }
+    }
+}
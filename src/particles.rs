@@ -0,0 +1,290 @@
+use std::f32::consts::PI;
+
+use macroquad::prelude::*;
+
+use crate::assets::{Assets, World};
+
+const MAX_PARTICLES: usize = 2048;
+
+pub enum EmitterMode {
+    /// Spawns this many particles every time it is emitted
+    Burst(u16),
+    /// Spawns this many particles per second of emission
+    Continuous(f32),
+}
+pub enum ParticleRender {
+    Rect,
+    /// Tile from the tileset, scaled by the particle size
+    Tile(f32, f32),
+}
+pub struct EmitterType {
+    pub mode: EmitterMode,
+    pub lifetime: (f32, f32),
+    pub speed: (f32, f32),
+    /// Total angle the particles are spread across, centered on the emit direction
+    pub spread: f32,
+    pub drag: f32,
+    pub gravity: Vec2,
+    pub color: (Color, Color),
+    pub size: (f32, f32),
+    pub collision: bool,
+    pub render: ParticleRender,
+}
+
+pub static MUZZLE_FLASH: EmitterType = EmitterType {
+    mode: EmitterMode::Burst(6),
+    lifetime: (0.05, 0.15),
+    speed: (40.0, 120.0),
+    spread: PI / 3.0,
+    drag: 12.0,
+    gravity: Vec2::ZERO,
+    color: (Color::from_hex(0xfff3b0), Color::new(0.53, 0.82, 0.94, 0.0)),
+    size: (2.0, 1.0),
+    collision: false,
+    render: ParticleRender::Rect,
+};
+pub static WALL_SPARKS: EmitterType = EmitterType {
+    mode: EmitterMode::Burst(8),
+    lifetime: (0.15, 0.4),
+    speed: (30.0, 110.0),
+    spread: PI,
+    drag: 6.0,
+    gravity: Vec2::new(0.0, 160.0),
+    color: (Color::from_hex(0xffffff), Color::new(0.53, 0.82, 0.94, 0.0)),
+    size: (1.0, 1.0),
+    collision: true,
+    render: ParticleRender::Rect,
+};
+pub static EXPLOSION: EmitterType = EmitterType {
+    mode: EmitterMode::Burst(24),
+    lifetime: (0.2, 0.5),
+    speed: (20.0, 90.0),
+    spread: PI * 2.0,
+    drag: 5.0,
+    gravity: Vec2::ZERO,
+    color: (Color::from_hex(0xffe080), Color::new(0.6, 0.2, 0.1, 0.0)),
+    size: (4.0, 2.0),
+    collision: false,
+    render: ParticleRender::Rect,
+};
+pub static EMERGE_DEBRIS: EmitterType = EmitterType {
+    mode: EmitterMode::Continuous(30.0),
+    lifetime: (0.2, 0.5),
+    speed: (15.0, 45.0),
+    spread: PI * 2.0,
+    drag: 4.0,
+    gravity: Vec2::new(0.0, 90.0),
+    color: (WHITE, Color::new(0.3, 0.3, 0.3, 0.0)),
+    size: (3.0, 1.0),
+    collision: false,
+    render: ParticleRender::Tile(0.0, 0.0),
+};
+pub static TELEPORT_SPARKS: EmitterType = EmitterType {
+    mode: EmitterMode::Burst(24),
+    lifetime: (0.2, 0.5),
+    speed: (20.0, 70.0),
+    spread: PI * 2.0,
+    drag: 5.0,
+    gravity: Vec2::new(0.0, -30.0),
+    color: (Color::from_hex(0xc080ff), Color::new(0.3, 0.5, 1.0, 0.0)),
+    size: (2.0, 1.0),
+    collision: false,
+    render: ParticleRender::Rect,
+};
+pub static THRUSTER_EXHAUST: EmitterType = EmitterType {
+    mode: EmitterMode::Continuous(120.0),
+    lifetime: (0.3, 0.8),
+    speed: (20.0, 60.0),
+    spread: PI / 4.0,
+    drag: 2.0,
+    gravity: Vec2::ZERO,
+    color: (Color::from_hex(0xffd27f), Color::new(0.4, 0.4, 0.45, 0.0)),
+    size: (3.0, 6.0),
+    collision: false,
+    render: ParticleRender::Rect,
+};
+pub static CRATE_DEBRIS: EmitterType = EmitterType {
+    mode: EmitterMode::Burst(14),
+    lifetime: (0.3, 0.7),
+    speed: (20.0, 80.0),
+    spread: PI * 2.0,
+    drag: 4.0,
+    gravity: Vec2::new(0.0, 120.0),
+    color: (Color::from_hex(0x9a6a3a), Color::new(0.35, 0.23, 0.13, 0.0)),
+    size: (3.0, 1.0),
+    collision: true,
+    render: ParticleRender::Rect,
+};
+pub static VENT_DEBRIS: EmitterType = EmitterType {
+    mode: EmitterMode::Burst(10),
+    lifetime: (0.3, 0.6),
+    speed: (20.0, 70.0),
+    spread: PI * 2.0,
+    drag: 4.0,
+    gravity: Vec2::new(0.0, 120.0),
+    color: (Color::from_hex(0x8a8a94), Color::new(0.23, 0.23, 0.25, 0.0)),
+    size: (2.0, 1.0),
+    collision: true,
+    render: ParticleRender::Rect,
+};
+pub static GLASS_SHARDS: EmitterType = EmitterType {
+    mode: EmitterMode::Burst(18),
+    lifetime: (0.3, 0.8),
+    speed: (30.0, 100.0),
+    spread: PI * 2.0,
+    drag: 3.0,
+    gravity: Vec2::new(0.0, 120.0),
+    color: (Color::from_hex(0xd8f0ff), Color::new(0.53, 0.82, 0.94, 0.0)),
+    size: (2.0, 1.0),
+    collision: false,
+    render: ParticleRender::Rect,
+};
+pub static BREACH_DUST: EmitterType = EmitterType {
+    mode: EmitterMode::Continuous(24.0),
+    lifetime: (0.4, 0.8),
+    speed: (50.0, 90.0),
+    spread: PI / 8.0,
+    drag: 0.0,
+    gravity: Vec2::ZERO,
+    color: (
+        Color::new(0.8, 0.85, 0.9, 0.6),
+        Color::new(0.53, 0.82, 0.94, 0.0),
+    ),
+    size: (1.0, 1.0),
+    collision: false,
+    render: ParticleRender::Rect,
+};
+pub static FLAMES: EmitterType = EmitterType {
+    mode: EmitterMode::Continuous(30.0),
+    lifetime: (0.3, 0.7),
+    speed: (10.0, 30.0),
+    spread: PI / 3.0,
+    drag: 1.0,
+    gravity: Vec2::new(0.0, -30.0),
+    color: (Color::from_hex(0xffd27f), Color::new(0.7, 0.2, 0.1, 0.0)),
+    size: (3.0, 1.0),
+    collision: false,
+    render: ParticleRender::Rect,
+};
+pub static ELECTRIC_ARCS: EmitterType = EmitterType {
+    mode: EmitterMode::Continuous(20.0),
+    lifetime: (0.05, 0.15),
+    speed: (20.0, 60.0),
+    spread: PI * 2.0,
+    drag: 6.0,
+    gravity: Vec2::ZERO,
+    color: (Color::from_hex(0xd8f0ff), Color::new(0.53, 0.82, 0.94, 0.0)),
+    size: (1.0, 1.0),
+    collision: false,
+    render: ParticleRender::Rect,
+};
+
+struct Particle {
+    ty: &'static EmitterType,
+    pos: Vec2,
+    velocity: Vec2,
+    time: f32,
+    lifetime: f32,
+}
+
+/// Pool of all live particles. Storage is allocated once up front, and new
+/// particles are dropped if the pool is full.
+pub struct Particles {
+    particles: Vec<Particle>,
+}
+impl Particles {
+    pub fn new() -> Self {
+        Self {
+            particles: Vec::with_capacity(MAX_PARTICLES),
+        }
+    }
+    /// Emits particles of a type at `pos` travelling roughly along `dir`.
+    /// Continuous emitters should be called every frame they are active.
+    pub fn emit(&mut self, ty: &'static EmitterType, pos: Vec2, dir: Vec2, delta_time: f32) {
+        let amount = match ty.mode {
+            EmitterMode::Burst(amount) => amount as usize,
+            EmitterMode::Continuous(rate) => {
+                (rate * delta_time + rand::gen_range(0.0, 1.0)) as usize
+            }
+        };
+        let base_angle = if dir.length_squared() > 0.0 {
+            dir.to_angle()
+        } else {
+            0.0
+        };
+        for _ in 0..amount {
+            if self.particles.len() >= MAX_PARTICLES {
+                return;
+            }
+            let angle = base_angle + rand::gen_range(-ty.spread / 2.0, ty.spread / 2.0);
+            self.particles.push(Particle {
+                ty,
+                pos,
+                velocity: Vec2::from_angle(angle) * rand::gen_range(ty.speed.0, ty.speed.1),
+                time: 0.0,
+                lifetime: rand::gen_range(ty.lifetime.0, ty.lifetime.1),
+            });
+        }
+    }
+    pub fn update(&mut self, delta_time: f32, world: &World) {
+        self.particles.retain_mut(|particle| {
+            particle.time += delta_time;
+            if particle.time >= particle.lifetime {
+                return false;
+            }
+            particle.velocity += particle.ty.gravity * delta_time;
+            particle.velocity = particle
+                .velocity
+                .lerp(Vec2::ZERO, (particle.ty.drag * delta_time).min(1.0));
+            let new = particle.pos + particle.velocity * delta_time;
+            if particle.ty.collision && world.is_solid(new) {
+                // bounce off whichever axis moved into the wall
+                if world.is_solid(vec2(new.x, particle.pos.y)) {
+                    particle.velocity.x *= -0.5;
+                }
+                if world.is_solid(vec2(particle.pos.x, new.y)) {
+                    particle.velocity.y *= -0.5;
+                }
+            } else {
+                particle.pos = new;
+            }
+            true
+        });
+    }
+    pub fn draw(&self, assets: &Assets) {
+        for particle in &self.particles {
+            let amt = particle.time / particle.lifetime;
+            let (from, to) = particle.ty.color;
+            let color = Color::new(
+                from.r + (to.r - from.r) * amt,
+                from.g + (to.g - from.g) * amt,
+                from.b + (to.b - from.b) * amt,
+                from.a + (to.a - from.a) * amt,
+            );
+            let size = particle.ty.size.0 + (particle.ty.size.1 - particle.ty.size.0) * amt;
+            let pos = (particle.pos - size / 2.0).floor();
+            match particle.ty.render {
+                ParticleRender::Rect => draw_rectangle(pos.x, pos.y, size, size, color),
+                ParticleRender::Tile(tile_x, tile_y) => {
+                    let sprite_size = assets.tileset.sprite_size;
+                    draw_texture_ex(
+                        &assets.tileset.texture,
+                        pos.x,
+                        pos.y,
+                        color,
+                        DrawTextureParams {
+                            dest_size: Some(vec2(size, size)),
+                            source: Some(Rect {
+                                x: tile_x * sprite_size,
+                                y: tile_y * sprite_size,
+                                w: sprite_size,
+                                h: sprite_size,
+                            }),
+                            ..Default::default()
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::{collections::HashMap, f32::consts::PI, sync::LazyLock};
+
+use macroquad::prelude::*;
+
+use crate::player::{
+    ALIEN_BALL, ALIEN_BOMB, ALIEN_SEEKER, ENERGY_BALL, ENERGY_SHOT, Projectile, ProjectileType,
+};
+
+/// Projectiles that patterns can refer to by name
+static PROJECTILES: &[(&str, &ProjectileType)] = &[
+    ("energy_ball", &ENERGY_BALL),
+    ("energy_shot", &ENERGY_SHOT),
+    ("alien_ball", &ALIEN_BALL),
+    ("alien_seeker", &ALIEN_SEEKER),
+    ("alien_bomb", &ALIEN_BOMB),
+];
+
+/// Most commands a pattern can run in a single frame, so a pattern with no waits can't hang the game
+const MAX_STEPS: usize = 1000;
+
+pub static PATTERNS: LazyLock<HashMap<&'static str, Pattern>> =
+    LazyLock::new(|| parse_patterns(include_str!("../assets/patterns.txt")));
+
+enum Command {
+    Fire {
+        projectile: &'static ProjectileType,
+        aim: bool,
+        angle: f32,
+        spread: f32,
+        count: u16,
+        speed: f32,
+        then: Option<&'static str>,
+    },
+    Turn(f32),
+    Aim,
+    Wait(f32),
+    Sync(f32),
+    Repeat(u16, Vec<Command>),
+}
+
+pub struct Pattern {
+    commands: Vec<Command>,
+}
+
+/// A pattern being played from some position
+pub struct PatternRunner {
+    /// Commands being run, along with the next command and repeats left for each level of nesting
+    stack: Vec<(&'static [Command], usize, u16)>,
+    wait: f32,
+    angle: f32,
+    pub pos: Vec2,
+}
+impl PatternRunner {
+    pub fn new(name: &str, pos: Vec2, dir: Vec2) -> Self {
+        let pattern = PATTERNS
+            .get(name)
+            .unwrap_or_else(|| panic!("no bullet pattern named {name}"));
+        Self {
+            stack: vec![(&pattern.commands, 0, 0)],
+            wait: 0.0,
+            angle: dir.to_angle(),
+            pos,
+        }
+    }
+    /// Runs the pattern, returning whether it is still going.
+    /// `progress` is how far through its animation the firing enemy state is, if any.
+    pub fn update(
+        &mut self,
+        delta_time: f32,
+        target: Vec2,
+        progress: Option<f32>,
+        projectiles: &mut Vec<Projectile>,
+    ) -> bool {
+        self.wait -= delta_time;
+        for _ in 0..MAX_STEPS {
+            if self.wait > 0.0 {
+                return true;
+            }
+            let Some((commands, index, repeats)) = self.stack.last_mut() else {
+                return false;
+            };
+            let Some(command) = commands.get(*index) else {
+                if *repeats > 1 {
+                    *repeats -= 1;
+                    *index = 0;
+                } else {
+                    self.stack.pop();
+                }
+                continue;
+            };
+            match command {
+                Command::Sync(fraction) => {
+                    if progress.is_some_and(|f| f < *fraction) {
+                        return true;
+                    }
+                }
+                Command::Wait(time) => self.wait += time,
+                Command::Turn(angle) => self.angle += angle,
+                Command::Aim => self.angle = (target - self.pos).to_angle(),
+                Command::Fire {
+                    projectile,
+                    aim,
+                    angle,
+                    spread,
+                    count,
+                    speed,
+                    then,
+                } => {
+                    let base = if *aim {
+                        (target - self.pos).to_angle()
+                    } else {
+                        self.angle
+                    } + angle;
+                    // a single bullet goes straight ahead, whatever the spread
+                    let (first, per_angle) = if *count > 1 {
+                        (base - spread / 2.0, spread / (*count - 1) as f32)
+                    } else {
+                        (base, 0.0)
+                    };
+                    for i in 0..*count {
+                        let angle = first + per_angle * i as f32;
+                        let mut new =
+                            Projectile::new(projectile, self.pos, Vec2::from_angle(angle), false);
+                        new.speed *= speed;
+                        new.spawns = *then;
+                        projectiles.push(new);
+                    }
+                }
+                Command::Repeat(_, _) => {}
+            }
+            *index += 1;
+            if let Command::Repeat(amount, commands) = command
+                && *amount > 0
+            {
+                self.stack.push((commands, 0, *amount));
+            }
+        }
+        true
+    }
+}
+
+fn parse_patterns(source: &'static str) -> HashMap<&'static str, Pattern> {
+    let mut patterns = HashMap::new();
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line.split('#').next().unwrap().trim()))
+        .filter(|(_, line)| !line.is_empty());
+    while let Some((number, line)) = lines.next() {
+        let Some(name) = line.strip_prefix("pattern ") else {
+            panic!("line {number}: expected `pattern <name>`, found `{line}`");
+        };
+        let commands = parse_commands(&mut lines, "end");
+        patterns.insert(name.trim(), Pattern { commands });
+    }
+    // make sure bullets don't spawn patterns that don't exist
+    fn check(commands: &[Command], patterns: &HashMap<&str, Pattern>) {
+        for command in commands {
+            match command {
+                Command::Fire {
+                    then: Some(then), ..
+                } if !patterns.contains_key(then) => {
+                    panic!("no bullet pattern named {then}")
+                }
+                Command::Repeat(_, commands) => check(commands, patterns),
+                _ => {}
+            }
+        }
+    }
+    for pattern in patterns.values() {
+        check(&pattern.commands, &patterns);
+    }
+    patterns
+}
+
+fn parse_commands(
+    lines: &mut impl Iterator<Item = (usize, &'static str)>,
+    terminator: &str,
+) -> Vec<Command> {
+    let mut commands = Vec::new();
+    while let Some((number, line)) = lines.next() {
+        if line == terminator {
+            return commands;
+        }
+        let mut words = line.split_whitespace();
+        let error = |message: &str| -> ! { panic!("line {number}: {message} in `{line}`") };
+        let number_arg = |words: &mut std::str::SplitWhitespace| -> f32 {
+            words
+                .next()
+                .and_then(|f| f.parse().ok())
+                .unwrap_or_else(|| error("expected a number"))
+        };
+        let command = match words.next().unwrap() {
+            "turn" => Command::Turn(number_arg(&mut words).to_radians()),
+            "wait" => Command::Wait(number_arg(&mut words)),
+            "sync" => Command::Sync(number_arg(&mut words)),
+            "aim" => Command::Aim,
+            "repeat" => {
+                let amount = number_arg(&mut words) as u16;
+                if words.next() != Some("{") {
+                    error("expected `{`");
+                }
+                Command::Repeat(amount, parse_commands(lines, "}"))
+            }
+            word @ ("fire" | "ring") => {
+                let name = words
+                    .next()
+                    .unwrap_or_else(|| error("expected a projectile"));
+                let projectile = PROJECTILES
+                    .iter()
+                    .find(|(f, _)| *f == name)
+                    .unwrap_or_else(|| error("unknown projectile"))
+                    .1;
+                let ring = word == "ring";
+                let mut fire = Command::Fire {
+                    projectile,
+                    aim: false,
+                    angle: 0.0,
+                    spread: 0.0,
+                    count: if ring {
+                        number_arg(&mut words) as u16
+                    } else {
+                        1
+                    },
+                    speed: 1.0,
+                    then: None,
+                };
+                let Command::Fire {
+                    aim,
+                    angle,
+                    spread,
+                    count,
+                    speed,
+                    then,
+                    ..
+                } = &mut fire
+                else {
+                    unreachable!()
+                };
+                while let Some(option) = words.next() {
+                    match option {
+                        "aim" if !ring => *aim = true,
+                        "angle" if !ring => *angle = number_arg(&mut words).to_radians(),
+                        "spread" if !ring => {
+                            *spread = number_arg(&mut words).to_radians();
+                            *count = number_arg(&mut words) as u16;
+                        }
+                        "speed" => *speed = number_arg(&mut words),
+                        "then" => {
+                            *then =
+                                Some(words.next().unwrap_or_else(|| error("expected a pattern")))
+                        }
+                        _ => error("unknown option"),
+                    }
+                }
+                if *count == 0 {
+                    error("expected at least one bullet");
+                }
+                if ring {
+                    // leave a gap so the first and last bullets don't overlap
+                    *spread = 2.0 * PI - 2.0 * PI / *count as f32;
+                }
+                fire
+            }
+            _ => error("unknown command"),
+        };
+        commands.push(command);
+    }
+    panic!("bullet patterns: missing `{terminator}`");
+}
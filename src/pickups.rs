@@ -0,0 +1,213 @@
+use macroquad::prelude::*;
+
+use crate::{assets::World, modifiers::WeaponModifier, player::Player};
+
+const MAGNET_RADIUS: f32 = 40.0;
+const MAGNET_SPEED: f32 = 160.0;
+const HEALTH_AMOUNT: f32 = 25.0;
+pub const BUFF_DURATION: f32 = 10.0;
+pub const SHIELD_AMOUNT: f32 = 30.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PickupKind {
+    Ammo,
+    Health,
+    DamageBoost,
+    FireRateBoost,
+    Shield,
+    Modifier(WeaponModifier),
+    Keycard(Keycard),
+}
+impl PickupKind {
+    /// Pickups placed in the "Interactable" layer start at tile 130
+    pub fn from_tile(tile: i16) -> Option<Self> {
+        Some(match tile {
+            130 => Self::Ammo,
+            131 => Self::Health,
+            132 => Self::DamageBoost,
+            133 => Self::FireRateBoost,
+            134 => Self::Shield,
+            _ => return None,
+        })
+    }
+    /// Draws the icon of this pickup, centered on `pos`, with each pixel `scale` wide
+    pub fn draw_icon(&self, pos: Vec2, scale: f32) {
+        let rect = |x: f32, y: f32, w: f32, h: f32, color: Color| {
+            draw_rectangle(
+                pos.x + x * scale,
+                pos.y + y * scale,
+                w * scale,
+                h * scale,
+                color,
+            );
+        };
+        match self {
+            PickupKind::Modifier(modifier) => return modifier.draw_icon(pos, scale),
+            PickupKind::Keycard(keycard) => return keycard.draw_icon(pos, scale),
+            _ => {}
+        }
+        rect(-5.0, -5.0, 10.0, 10.0, BLACK);
+        match self {
+            PickupKind::Ammo => {
+                rect(-3.0, -4.0, 6.0, 8.0, AMMO_COLOR);
+                rect(-3.0, 0.0, 6.0, 1.0, BLACK.with_alpha(0.4));
+            }
+            PickupKind::Health => {
+                rect(-4.0, -4.0, 8.0, 8.0, WHITE);
+                rect(-1.0, -3.0, 2.0, 6.0, HEALTH_COLOR);
+                rect(-3.0, -1.0, 6.0, 2.0, HEALTH_COLOR);
+            }
+            PickupKind::DamageBoost => {
+                rect(-1.0, -4.0, 2.0, 8.0, DAMAGE_COLOR);
+                rect(-3.0, -2.0, 6.0, 4.0, DAMAGE_COLOR);
+                rect(-4.0, -1.0, 8.0, 2.0, DAMAGE_COLOR);
+            }
+            PickupKind::FireRateBoost => {
+                rect(0.0, -4.0, 3.0, 4.0, FIRE_RATE_COLOR);
+                rect(-3.0, -1.0, 6.0, 2.0, FIRE_RATE_COLOR);
+                rect(-3.0, 0.0, 3.0, 4.0, FIRE_RATE_COLOR);
+            }
+            PickupKind::Shield => {
+                rect(-4.0, -4.0, 8.0, 5.0, SHIELD_COLOR);
+                rect(-3.0, 1.0, 6.0, 2.0, SHIELD_COLOR);
+                rect(-1.0, 3.0, 2.0, 1.0, SHIELD_COLOR);
+            }
+            PickupKind::Modifier(_) | PickupKind::Keycard(_) => {}
+        }
+    }
+}
+/// Opens doors of the same colour
+#[derive(Clone, Copy, PartialEq)]
+pub enum Keycard {
+    Red,
+    Blue,
+    Yellow,
+}
+impl Keycard {
+    /// Reads the name used for keycards in object properties
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "red" => Self::Red,
+            "blue" => Self::Blue,
+            "yellow" => Self::Yellow,
+            _ => panic!("no keycard named {name}"),
+        }
+    }
+    pub fn color(&self) -> Color {
+        match self {
+            Keycard::Red => KEYCARD_RED,
+            Keycard::Blue => KEYCARD_BLUE,
+            Keycard::Yellow => KEYCARD_YELLOW,
+        }
+    }
+    pub fn locked_tooltip(&self) -> &'static str {
+        match self {
+            Keycard::Red => "Needs red keycard",
+            Keycard::Blue => "Needs blue keycard",
+            Keycard::Yellow => "Needs yellow keycard",
+        }
+    }
+    pub fn draw_icon(&self, pos: Vec2, scale: f32) {
+        let rect = |x: f32, y: f32, w: f32, h: f32, color: Color| {
+            draw_rectangle(
+                pos.x + x * scale,
+                pos.y + y * scale,
+                w * scale,
+                h * scale,
+                color,
+            );
+        };
+        rect(-5.0, -4.0, 10.0, 8.0, BLACK);
+        rect(-4.0, -3.0, 8.0, 6.0, self.color());
+        rect(-3.0, -2.0, 3.0, 2.0, WHITE);
+        rect(-3.0, 1.0, 6.0, 1.0, BLACK.with_alpha(0.4));
+    }
+}
+
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub pos: Vec2,
+    pub time: f32,
+}
+impl Pickup {
+    pub fn new(kind: PickupKind, pos: Vec2) -> Self {
+        Self {
+            kind,
+            pos,
+            time: rand::gen_range(0.0, 10.0),
+        }
+    }
+    /// Gives the pickup to the player if they're touching it. Returns whether the pickup remains.
+    pub fn update(&mut self, player: &mut Player, world: &World, delta_time: f32) -> bool {
+        self.time += delta_time;
+        self.pos += world.breach_pull(self.pos).0 * delta_time;
+        if world.in_breach(self.pos) {
+            return false;
+        }
+        if !self.wanted_by(player) {
+            return true;
+        }
+        let target = player.pos + 8.0;
+        let distance = target.distance(self.pos);
+        if distance < MAGNET_RADIUS && distance > 0.0 {
+            let pull = 1.0 - distance / MAGNET_RADIUS;
+            self.pos +=
+                (target - self.pos) / distance * (MAGNET_SPEED * pull * delta_time).min(distance);
+        }
+        if distance > 12.0 {
+            return true;
+        }
+        match self.kind {
+            PickupKind::Ammo => player.inventory.add_ammo(),
+            PickupKind::Health => player.health = (player.health + HEALTH_AMOUNT).min(100.0),
+            PickupKind::DamageBoost | PickupKind::FireRateBoost | PickupKind::Shield => {
+                player.add_buff(self.kind)
+            }
+            PickupKind::Modifier(modifier) => {
+                player.inventory.add_modifier(modifier);
+            }
+            PickupKind::Keycard(keycard) => player.keycards.push(keycard),
+        }
+        false
+    }
+    /// Whether the player would get any use out of this pickup
+    fn wanted_by(&self, player: &Player) -> bool {
+        match self.kind {
+            PickupKind::Ammo => player
+                .inventory
+                .slots
+                .iter()
+                .any(|f| f.weapon.max_ammo.is_some()),
+            PickupKind::Modifier(_) => !player.inventory.slots.is_empty(),
+            PickupKind::Health => player.health < 100.0,
+            _ => true,
+        }
+    }
+    pub fn draw(&self) {
+        let bob = (self.time * 4.0).sin() * 2.0;
+        draw_ellipse(
+            self.pos.x.floor(),
+            self.pos.y.floor() + 7.0,
+            4.0 - bob / 2.0,
+            1.5,
+            0.0,
+            BLACK.with_alpha(0.4),
+        );
+        self.kind
+            .draw_icon(self.pos.floor() - vec2(0.0, 2.0 + bob.round()), 1.0);
+    }
+}
+
+pub struct Buff {
+    pub kind: PickupKind,
+    pub time: f32,
+}
+
+const AMMO_COLOR: Color = Color::from_hex(0xe0c050);
+const HEALTH_COLOR: Color = Color::from_hex(0xd04040);
+const DAMAGE_COLOR: Color = Color::from_hex(0xf08030);
+const FIRE_RATE_COLOR: Color = Color::from_hex(0xf0e040);
+pub const SHIELD_COLOR: Color = Color::from_hex(0x60a0f0);
+const KEYCARD_RED: Color = Color::from_hex(0xe04848);
+const KEYCARD_BLUE: Color = Color::from_hex(0x4878e0);
+const KEYCARD_YELLOW: Color = Color::from_hex(0xe8c838);
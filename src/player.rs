@@ -1,10 +1,17 @@
-use std::{borrow::Borrow, collections::HashMap, f32::consts::PI};
+use std::{borrow::Borrow, f32::consts::PI};
 
 use macroquad::prelude::*;
 
 use crate::{
-    assets::{Assets, BARRIER, Chunk, World},
-    enemy::{ENEMIES, Enemy},
+    assets::{Assets, Chunk, World},
+    companions::Companion,
+    enemy::{Enemy, HEARING_RANGE},
+    inventory::Inventory,
+    modifiers::{ProjectileModifiers, WeaponModifier},
+    particles::{EXPLOSION, MUZZLE_FLASH, Particles, WALL_SPARKS},
+    pickups::{BUFF_DURATION, Buff, Keycard, PickupKind, SHIELD_AMOUNT},
+    settings::settings,
+    status::{Damage, DamageType, StatusEffect, StatusKind, Statuses},
     utils::*,
 };
 
@@ -14,88 +21,223 @@ fn tile_to_chunk(pos: (i16, i16)) -> (i16, i16) {
     (cx, cy)
 }
 
-fn vec2_to_tile(pos: Vec2) -> (i16, i16) {
+pub fn vec2_to_tile(pos: Vec2) -> (i16, i16) {
     let cx = (pos.x / 16.0).floor() as i16;
     let cy = (pos.y / 16.0).floor() as i16;
     (cx, cy)
 }
 
-fn get_connected_spawners(chunks: &[Chunk], start: (i16, i16)) -> Vec<((i16, i16), i16)> {
-    fn recurse(
-        chunks: &[Chunk],
-        start: (i16, i16),
-        checked: &mut Vec<(i16, i16)>,
-        result: &mut Vec<((i16, i16), i16)>,
-    ) {
-        let dirs = [(0, 1), (0, -1), (1, 0), (-1, 0)];
-        for dir in dirs {
-            let pos = (start.0 + dir.0, start.1 + dir.1);
-            if checked.contains(&pos) {
-                continue;
-            }
-
-            let tile = get_tile(chunks, pos.0, pos.1) - 1;
-            if tile > -1 {
-                checked.push(pos);
-                recurse(chunks, pos, checked, result);
-                if tile != 32 {
-                    result.push(((pos.0, pos.1), tile));
-                }
-            }
-        }
-    }
-    let mut result = Vec::new();
-    recurse(chunks, start, &mut Vec::new(), &mut result);
-    result
+#[derive(Clone, Copy, PartialEq)]
+pub enum HomingTarget {
+    /// Closest target to the projectile
+    Nearest,
+    /// Target most in line with the direction the projectile is already going
+    Forward,
 }
-
+#[derive(PartialEq)]
+pub struct ProjectileBehaviour {
+    /// Seconds until the projectile expires
+    pub lifetime: Option<f32>,
+    /// Distance the projectile can travel before it expires
+    pub range: Option<f32>,
+    /// Change in speed per second
+    pub acceleration: f32,
+    pub bounces: u8,
+    pub pierce: u8,
+    /// How quickly the projectile turns towards its target, or 0 for no homing
+    pub homing: f32,
+    pub target: HomingTarget,
+    /// Radius of splash damage dealt when the projectile expires, or 0 for none
+    pub explosion_radius: f32,
+}
+/// Flies straight until it hits something
+pub const STRAIGHT: ProjectileBehaviour = ProjectileBehaviour {
+    lifetime: None,
+    range: None,
+    acceleration: 0.0,
+    bounces: 0,
+    pierce: 0,
+    homing: 0.0,
+    target: HomingTarget::Nearest,
+    explosion_radius: 0.0,
+};
 #[derive(PartialEq)]
 pub struct ProjectileType {
     pub animation_index: usize,
     pub speed: f32,
     pub damage: f32,
+    pub damage_type: DamageType,
+    /// Status effect inflicted on whatever it hits
+    pub effect: Option<StatusEffect>,
+    /// Radius and colour of the glow this projectile gives off
+    pub light: Option<(f32, Color)>,
+    pub behaviour: ProjectileBehaviour,
 }
 pub struct Projectile {
     pub ty: &'static ProjectileType,
     pub pos: Vec2,
     pub dir: Vec2,
+    pub speed: f32,
     pub time: f32,
+    /// Distance travelled so far
+    pub travelled: f32,
     pub friendly: bool,
+    /// Where the projectile was fired from
+    pub origin: Vec2,
+    pub damage: f32,
+    pub modifiers: ProjectileModifiers,
+    /// Enemies this projectile has already passed through
+    pub hit: Vec<u32>,
+    /// Whether this hostile projectile has hit the player directly
+    pub hit_player: bool,
+    /// Bullet pattern to start where this projectile expires
+    pub spawns: Option<&'static str>,
 }
 impl Projectile {
+    pub fn new(ty: &'static ProjectileType, pos: Vec2, dir: Vec2, friendly: bool) -> Self {
+        Self {
+            ty,
+            pos,
+            dir,
+            speed: if friendly {
+                ty.speed
+            } else {
+                settings().hostile_projectile_speed(ty.speed)
+            },
+            time: 0.0,
+            travelled: 0.0,
+            friendly,
+            origin: pos,
+            damage: if friendly {
+                ty.damage
+            } else {
+                settings().enemy_damage(ty.damage)
+            },
+            modifiers: ProjectileModifiers::from_behaviour(&ty.behaviour),
+            hit: Vec::new(),
+            hit_player: false,
+            spawns: None,
+        }
+    }
+    #[expect(clippy::too_many_arguments)]
     pub fn update(
         &mut self,
         assets: &Assets,
         enemies: &mut [Enemy],
         player: &mut Player,
-        world: &World,
+        companions: &mut [Companion],
+        world: &mut World,
+        particles: &mut Particles,
         delta_time: f32,
     ) -> bool {
-        self.pos += self.dir * self.ty.speed * delta_time;
+        self.time += delta_time;
+        if self.ty.behaviour.lifetime.is_some_and(|f| self.time >= f)
+            || self.ty.behaviour.range.is_some_and(|f| self.travelled >= f)
+        {
+            self.explode(enemies, player, companions, world, particles);
+            return false;
+        }
+        if self.modifiers.homing > 0.0
+            && let Some(target) = self.homing_target(enemies, player)
+        {
+            self.dir = self
+                .dir
+                .lerp(
+                    (target - self.pos).normalize_or(self.dir),
+                    (self.modifiers.homing * delta_time).min(1.0),
+                )
+                .normalize_or(self.dir);
+        }
+        self.speed = (self.speed + self.ty.behaviour.acceleration * delta_time).max(0.0);
+        let old = self.pos;
+        self.pos += self.dir * self.speed * delta_time;
+        self.pos += world.breach_pull(self.pos).0 * delta_time;
+        self.travelled += self.speed * delta_time;
 
         if self.friendly {
-            if let Some(enemy) = enemies
-                .iter_mut()
-                .find(|enemy| enemy.pos.distance_squared(self.pos) < 256.0)
-            {
+            if let Some(enemy) = enemies.iter_mut().find(|enemy| {
+                enemy.pos.distance_squared(self.pos) < 256.0 && !self.hit.contains(&enemy.id)
+            }) {
                 if enemy.emerging {
                     return false;
                 }
-                enemy.health -= self.ty.damage;
-                return false;
+                enemy.hit(self.hit_damage(1.0), true);
+                enemy.hear(self.origin);
+                self.hit.push(enemy.id);
+                if self.modifiers.pierce == 0 {
+                    self.explode(enemies, player, companions, world, particles);
+                    return false;
+                }
+                self.modifiers.pierce -= 1;
             }
         } else if player.pos.distance_squared(self.pos) < 256.0 {
-            player.health -= self.ty.damage;
+            player.hit(self.hit_damage(1.0));
+            self.hit_player = true;
+            self.explode(enemies, player, companions, world, particles);
+            return false;
+        } else if let Some(companion) = companions
+            .iter_mut()
+            .filter(|f| f.pos.distance_squared(self.pos) < 256.0)
+            .min_by(|a, b| {
+                a.pos
+                    .distance_squared(self.pos)
+                    .total_cmp(&b.pos.distance_squared(self.pos))
+            })
+        {
+            companion.hit(self.hit_damage(1.0));
+            self.explode(enemies, player, companions, world, particles);
             return false;
         }
 
         let (tx, ty) = vec2_to_tile(self.pos);
+        if let Some(entity) = world.tile_entities.get_mut(&(tx, ty))
+            && let Some(on_projectile_hit) = entity.kind.on_projectile_hit
+            && on_projectile_hit(entity, self, particles)
+        {
+            particles.emit(&WALL_SPARKS, old, -self.dir, delta_time);
+            self.explode(enemies, player, companions, world, particles);
+            return false;
+        }
+        if world
+            .tile_entities
+            .get(&(tx, ty))
+            .is_some_and(|f| f.enabled && f.kind.destructible.is_some())
+        {
+            world.damage_tile(tx, ty, self.damage, particles);
+            // from where it was before entering the tile, so the sparks don't start inside it
+            particles.emit(&WALL_SPARKS, old, -self.dir, delta_time);
+            self.explode(enemies, player, companions, world, particles);
+            return false;
+        }
         let (cx, cy) = tile_to_chunk((tx, ty));
         if let Some(chunk) = world.collision.iter().find(|f| f.x == cx && f.y == cy)
             && let Some(tile) = chunk.tile_at((tx - cx) as _, (ty - cy) as _).map(|f| f - 1)
             && tile > -1
         {
-            return false;
+            world.damage_tile(tx, ty, self.damage, particles);
+            particles.emit(&WALL_SPARKS, old, -self.dir, delta_time);
+            if self.modifiers.bounces == 0 {
+                self.explode(enemies, player, companions, world, particles);
+                return false;
+            }
+            self.modifiers.bounces -= 1;
+            // reflect off the normal of whichever side of the tile was entered
+            let (old_x, old_y) = vec2_to_tile(old);
+            let mut normal = Vec2::ZERO;
+            if old_x != tx && world.is_solid(vec2(self.pos.x, old.y)) {
+                normal.x = (old_x - tx) as f32;
+            }
+            if old_y != ty && world.is_solid(vec2(old.x, self.pos.y)) {
+                normal.y = (old_y - ty) as f32;
+            }
+            self.dir = if normal == Vec2::ZERO {
+                // hit a corner head on
+                -self.dir
+            } else {
+                let normal = normal.normalize();
+                self.dir - 2.0 * self.dir.dot(normal) * normal
+            };
+            self.pos = old;
         }
         draw_texture_ex(
             assets.projectiles.animations[self.ty.animation_index]
@@ -110,48 +252,214 @@ impl Projectile {
         );
         true
     }
+    /// Damage dealt to whatever the projectile hits, scaled by `scale`
+    fn hit_damage(&self, scale: f32) -> Damage {
+        Damage {
+            amount: self.damage * scale,
+            ty: self.ty.damage_type,
+            effect: self.ty.effect,
+        }
+    }
+    fn homing_target(&self, enemies: &[Enemy], player: &Player) -> Option<Vec2> {
+        if !self.friendly {
+            return Some(player.pos + 8.0);
+        }
+        let candidates = enemies
+            .iter()
+            .filter(|f| !f.emerging && !self.hit.contains(&f.id))
+            .map(|f| f.pos)
+            .filter(|f| f.distance_squared(self.pos) < HOMING_RANGE * HOMING_RANGE);
+        match self.modifiers.target {
+            HomingTarget::Nearest => candidates.min_by(|a, b| {
+                a.distance_squared(self.pos)
+                    .total_cmp(&b.distance_squared(self.pos))
+            }),
+            HomingTarget::Forward => candidates.max_by(|a, b| {
+                let a = (*a - self.pos).normalize_or_zero().dot(self.dir);
+                let b = (*b - self.pos).normalize_or_zero().dot(self.dir);
+                a.total_cmp(&b)
+            }),
+        }
+    }
+    /// Deals splash damage around the projectile, if it is explosive
+    fn explode(
+        &self,
+        enemies: &mut [Enemy],
+        player: &mut Player,
+        companions: &mut [Companion],
+        world: &mut World,
+        particles: &mut Particles,
+    ) {
+        let radius = self.modifiers.explosion_radius;
+        if radius <= 0.0 {
+            return;
+        }
+        particles.emit(&EXPLOSION, self.pos, Vec2::ZERO, 0.0);
+        let (x1, y1) = vec2_to_tile(self.pos - radius);
+        let (x2, y2) = vec2_to_tile(self.pos + radius);
+        // the tile the projectile is in already took the direct hit
+        let direct = vec2_to_tile(self.pos);
+        for x in x1..=x2 {
+            for y in y1..=y2 {
+                if (x, y) == direct {
+                    continue;
+                }
+                let distance = (vec2(x as f32, y as f32) * 16.0 + 8.0).distance(self.pos);
+                if distance < radius {
+                    let amount = self.damage * (1.0 - distance / radius / 2.0);
+                    world.damage_tile(x, y, amount, particles);
+                }
+            }
+        }
+        if !self.friendly {
+            let distance = (player.pos + 8.0).distance(self.pos);
+            if distance < radius && !self.hit_player {
+                player.hit(Damage {
+                    ty: DamageType::Explosive,
+                    ..self.hit_damage(1.0 - distance / radius / 2.0)
+                });
+            }
+            for companion in companions.iter_mut() {
+                let distance = (companion.pos + 8.0).distance(self.pos);
+                if distance < radius {
+                    companion.hit(Damage {
+                        ty: DamageType::Explosive,
+                        ..self.hit_damage(1.0 - distance / radius / 2.0)
+                    });
+                }
+            }
+            return;
+        }
+        // enemies hit directly aren't caught in the blast as well
+        for enemy in enemies
+            .iter_mut()
+            .filter(|f| !f.emerging && !self.hit.contains(&f.id))
+        {
+            let distance = enemy.pos.distance(self.pos);
+            if distance < radius {
+                enemy.hit(
+                    Damage {
+                        ty: DamageType::Explosive,
+                        ..self.hit_damage(1.0 - distance / radius / 2.0)
+                    },
+                    true,
+                );
+            }
+        }
+    }
 }
+const HOMING_RANGE: f32 = 120.0;
 
 pub static ENERGY_BALL: ProjectileType = ProjectileType {
     animation_index: 0,
     speed: 160.0,
     damage: 4.0,
+    damage_type: DamageType::Energy,
+    effect: None,
+    light: Some((28.0, Color::new(0.35, 0.6, 0.75, 1.0))),
+    behaviour: STRAIGHT,
 };
 pub static ENERGY_SHOT: ProjectileType = ProjectileType {
     animation_index: 2,
     speed: 200.0,
     damage: 2.0,
+    damage_type: DamageType::Energy,
+    effect: None,
+    light: None,
+    behaviour: STRAIGHT,
 };
 pub static ALIEN_BALL: ProjectileType = ProjectileType {
     animation_index: 1,
     speed: 100.0,
     damage: 4.0,
+    damage_type: DamageType::Acid,
+    effect: None,
+    light: Some((28.0, Color::new(0.45, 0.7, 0.2, 1.0))),
+    behaviour: STRAIGHT,
+};
+/// Fired by blinkers, and freezes whatever it hits for a moment
+pub static FROST_BALL: ProjectileType = ProjectileType {
+    animation_index: 1,
+    speed: 100.0,
+    damage: 4.0,
+    damage_type: DamageType::Energy,
+    effect: Some(StatusEffect {
+        kind: StatusKind::Freeze,
+        duration: 0.6,
+    }),
+    light: Some((28.0, Color::new(0.4, 0.6, 0.8, 1.0))),
+    behaviour: STRAIGHT,
+};
+pub static ALIEN_SEEKER: ProjectileType = ProjectileType {
+    animation_index: 1,
+    speed: 60.0,
+    damage: 6.0,
+    damage_type: DamageType::Energy,
+    effect: None,
+    light: Some((36.0, Color::new(0.55, 0.75, 0.2, 1.0))),
+    behaviour: ProjectileBehaviour {
+        lifetime: Some(4.0),
+        acceleration: 15.0,
+        homing: 1.5,
+        ..STRAIGHT
+    },
+};
+pub static ALIEN_BOMB: ProjectileType = ProjectileType {
+    animation_index: 1,
+    speed: 110.0,
+    damage: 6.0,
+    damage_type: DamageType::Explosive,
+    effect: None,
+    light: Some((40.0, Color::new(0.7, 0.75, 0.2, 1.0))),
+    behaviour: ProjectileBehaviour {
+        lifetime: Some(1.2),
+        acceleration: -80.0,
+        ..STRAIGHT
+    },
 };
 #[derive(PartialEq)]
 pub struct Weapon {
     pub projectile: &'static ProjectileType,
     pub attack_delay: f32,
     pub multishot: Option<(u8, f32)>,
+    pub magazine: u16,
+    pub reload_time: f32,
+    /// Most spare ammo that can be carried, or `None` for unlimited
+    pub max_ammo: Option<u16>,
 }
 pub static GUN: Weapon = Weapon {
     projectile: &ENERGY_BALL,
     attack_delay: 1.0 / 3.0,
     multishot: None,
+    magazine: 12,
+    reload_time: 0.8,
+    max_ammo: None,
 };
 pub static RIFLE: Weapon = Weapon {
     projectile: &ENERGY_SHOT,
     attack_delay: 1.0 / 7.0,
     multishot: None,
+    magazine: 30,
+    reload_time: 1.4,
+    max_ammo: Some(180),
 };
 pub static SHOTGUN: Weapon = Weapon {
     projectile: &ENERGY_BALL,
     attack_delay: 0.6,
     multishot: Some((3, PI / 5.0)),
+    magazine: 6,
+    reload_time: 1.2,
+    max_ammo: Some(36),
 };
 pub static WEAPONS: &[&Weapon] = &[&GUN, &RIFLE, &SHOTGUN];
+/// Oxygen lost per second right next to an open breach
+const OXYGEN_DRAIN: f32 = 15.0;
+const OXYGEN_REFILL: f32 = 25.0;
+/// Damage per second once the player is out of oxygen
+const SUFFOCATION_DAMAGE: f32 = 10.0;
 
 pub struct Player {
-    pub weapon: Option<&'static Weapon>,
+    pub inventory: Inventory,
     pub pos: Vec2,
     pub camera_pos: Vec2,
     pub velocity: Vec2,
@@ -159,13 +467,18 @@ pub struct Player {
     pub walking: bool,
     pub moving_left: bool,
     pub health: f32,
-    pub spawned_spawners: Vec<(i16, i16)>,
     pub attack_counter: f32,
+    pub buffs: Vec<Buff>,
+    pub shield: f32,
+    pub keycards: Vec<Keycard>,
+    /// Drains near open breaches, and the player suffocates once it runs out
+    pub oxygen: f32,
+    pub statuses: Statuses,
 }
 impl Player {
     pub fn new() -> Self {
         Self {
-            weapon: None,
+            inventory: Inventory::new(),
             pos: Vec2::ZERO,
             camera_pos: Vec2::ZERO,
             velocity: Vec2::ZERO,
@@ -173,21 +486,64 @@ impl Player {
             walking: false,
             moving_left: false,
             health: 100.0,
-            spawned_spawners: Vec::new(),
             attack_counter: 0.0,
+            buffs: Vec::new(),
+            shield: 0.0,
+            keycards: Vec::new(),
+            oxygen: 100.0,
+            statuses: Statuses::default(),
+        }
+    }
+    pub fn has_buff(&self, kind: PickupKind) -> bool {
+        self.buffs.iter().any(|f| f.kind == kind)
+    }
+    pub fn add_buff(&mut self, kind: PickupKind) {
+        if kind == PickupKind::Shield {
+            self.shield = SHIELD_AMOUNT;
+        }
+        if let Some(buff) = self.buffs.iter_mut().find(|f| f.kind == kind) {
+            buff.time = BUFF_DURATION;
+        } else {
+            self.buffs.push(Buff {
+                kind,
+                time: BUFF_DURATION,
+            });
+        }
+    }
+    /// Deals damage to the player, going through their shield first
+    pub fn damage(&mut self, amount: f32) {
+        let amount = settings().damage_to_player(amount);
+        let absorbed = amount.min(self.shield);
+        self.shield -= absorbed;
+        self.health -= amount - absorbed;
+    }
+    /// Deals typed damage to the player and inflicts its status effect
+    pub fn hit(&mut self, damage: Damage) {
+        self.damage(damage.amount);
+        if let Some(effect) = damage.effect {
+            self.statuses.apply(effect);
         }
     }
     pub fn update(
         &mut self,
         delta_time: f32,
-        world: &mut World,
-        enemies: &mut Vec<Enemy>,
+        world: &World,
+        enemies: &mut [Enemy],
         projectiles: &mut Vec<Projectile>,
+        particles: &mut Particles,
         mouse: (f32, f32),
     ) {
         self.animation_time += delta_time;
         self.walking = false;
-        let axis = get_input_axis();
+        for (amount, _) in self.statuses.update(delta_time) {
+            self.damage(amount);
+        }
+        let stunned = self.statuses.stunned();
+        let axis = if stunned {
+            Vec2::ZERO
+        } else {
+            get_input_axis()
+        };
         if axis.length() > 0.0 {
             self.walking = true;
             if axis.x < 0.0 {
@@ -198,80 +554,91 @@ impl Player {
             self.velocity += axis.normalize() * delta_time * 3600.0;
         }
         self.attack_counter -= delta_time;
+        self.inventory.update(delta_time);
+        for buff in self.buffs.iter_mut() {
+            buff.time -= delta_time;
+        }
+        let shield = self.shield;
+        self.buffs
+            .retain(|f| f.time > 0.0 && (f.kind != PickupKind::Shield || shield > 0.0));
+        if !self.has_buff(PickupKind::Shield) {
+            self.shield = 0.0;
+        }
         if self.attack_counter <= 0.0
-            && let Some(weapon) = self.weapon
+            && !stunned
             && is_mouse_button_down(MouseButton::Left)
+            && let Some(weapon) = self.inventory.fire()
         {
+            let modifiers = &self.inventory.current().unwrap().modifiers;
+            let extra = modifiers
+                .iter()
+                .filter(|f| **f == WeaponModifier::ExtraProjectile)
+                .count() as u8;
+            let focus = modifiers
+                .iter()
+                .filter(|f| **f == WeaponModifier::Focused)
+                .count() as i32;
             self.attack_counter = weapon.attack_delay;
+            if self.has_buff(PickupKind::FireRateBoost) {
+                self.attack_counter /= 2.0;
+            }
+            let damage = if self.has_buff(PickupKind::DamageBoost) {
+                weapon.projectile.damage * 1.5
+            } else {
+                weapon.projectile.damage
+            };
             let mut new = Vec::new();
             let multishot = weapon.multishot.unwrap_or((1, 0.0));
+            let multishot = (
+                multishot.0 + extra,
+                (multishot.1 + extra as f32 * PI / 16.0) * 0.6_f32.powi(focus),
+            );
             let per_angle = multishot.1 / multishot.0 as f32;
+            let aim = (vec2(mouse.0, mouse.1) - vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0))
+                .normalize();
+            particles.emit(&MUZZLE_FLASH, self.pos + 8.0 + aim * 10.0, aim, delta_time);
             for i in 0..multishot.0 {
-                let angle = (vec2(mouse.0, mouse.1)
-                    - vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0))
-                .normalize()
-                .to_angle()
-                    + i as f32 * per_angle
-                    - per_angle * multishot.0 as f32 / 2.0;
+                let angle =
+                    aim.to_angle() + i as f32 * per_angle - per_angle * multishot.0 as f32 / 2.0;
 
-                new.push(Projectile {
-                    ty: weapon.projectile,
-                    time: 0.0,
-                    pos: self.pos + 8.0,
-                    dir: Vec2::from_angle(angle),
-                    friendly: true,
-                });
+                let mut projectile = Projectile::new(
+                    weapon.projectile,
+                    self.pos + 8.0,
+                    Vec2::from_angle(angle),
+                    true,
+                );
+                projectile.damage = damage;
+                projectile.modifiers.apply(modifiers);
+                new.push(projectile);
             }
             projectiles.append(&mut new);
+            for enemy in enemies
+                .iter_mut()
+                .filter(|f| f.pos.distance(self.pos + 8.0) < HEARING_RANGE)
+            {
+                enemy.hear(self.pos + 8.0);
+            }
         }
         let friction = if axis.length() == 0.0 { 20.0 } else { 10.0 } * delta_time;
         self.velocity = self
             .velocity
-            .clamp_length_max(2.0 * 70.0)
+            .clamp_length_max(2.0 * 70.0 * self.statuses.speed())
             .lerp(Vec2::ZERO, friction);
         let new = update_physicsbody(self.pos, &mut self.velocity, delta_time, world);
         self.walking &= self.velocity.length_squared() > 0.1;
         self.pos = new;
-        let (tx, ty) = vec2_to_tile(self.pos);
-        let (cx, cy) = tile_to_chunk((tx, ty));
-        let mut new_spawned = Vec::new();
-        let mut tile_entities = HashMap::new();
-        let mut new_enemies = Vec::new();
-        std::mem::swap(&mut tile_entities, &mut world.tile_entities);
-        if let Some(chunk) = world.interactable.iter().find(|f| f.x == cx && f.y == cy)
-            && let Some(tile) = chunk.tile_at((tx - cx) as _, (ty - cy) as _).map(|f| f - 1)
-            && tile > -1
-            && tile == 32
-        {
-            let tiles = get_connected_spawners(&world.interactable, (tx, ty));
-            for ((x, y), tile) in tiles
-                .into_iter()
-                .filter(|(p, _)| !self.spawned_spawners.contains(p))
-            {
-                match tile {
-                    96..111 => {
-                        new_spawned.push((x, y));
-                        let enemy = Enemy::new(
-                            &ENEMIES[tile as usize - 96],
-                            vec2(x as f32 * 16.0, y as f32 * 16.0),
-                        );
-                        new_enemies.push(enemy);
-                    }
-                    64 => {
-                        if enemies.is_empty() && self.weapon.is_some() {
-                            tile_entities.retain(|p, _| p != &(x, y));
-                        }
-                    }
-                    82 | 83 => {
-                        tile_entities.insert((x, y), BARRIER.instantiate(tile));
-                    }
-                    _ => panic!(),
-                }
-            }
+        let (mut pull, exposure) = world.breach_pull(self.pos + 8.0);
+        if pull != Vec2::ZERO {
+            self.pos = update_physicsbody(self.pos, &mut pull, delta_time, world);
+        }
+        if exposure > 0.0 {
+            self.oxygen = (self.oxygen - OXYGEN_DRAIN * exposure * delta_time).max(0.0);
+        } else {
+            self.oxygen = (self.oxygen + OXYGEN_REFILL * delta_time).min(100.0);
+        }
+        if self.oxygen <= 0.0 {
+            self.damage(SUFFOCATION_DAMAGE * delta_time);
         }
-        enemies.append(&mut new_enemies);
-        std::mem::swap(&mut tile_entities, &mut world.tile_entities);
-        self.spawned_spawners.append(&mut new_spawned);
         self.camera_pos = self.pos
     }
     pub fn draw(&self, assets: &Assets, mouse: (f32, f32)) {
@@ -280,17 +647,17 @@ impl Player {
                 .get_at_time((self.animation_time * 1000.0) as u32),
             self.pos.x.floor(),
             self.pos.y.floor(),
-            WHITE,
+            self.statuses.tint(),
             DrawTextureParams {
                 flip_x: mouse.0 < SCREEN_WIDTH / 2.0,
                 ..Default::default()
             },
         );
-        if let Some(weapon) = &self.weapon {
+        if let Some(slot) = self.inventory.current() {
             assets.tileset.draw_tile(
                 self.pos.x.floor() + 7.0,
                 self.pos.y.floor(),
-                WEAPONS.iter().position(|f| f == weapon).unwrap() as f32,
+                WEAPONS.iter().position(|f| *f == slot.weapon).unwrap() as f32,
                 7.0,
                 Some(&DrawTextureParams {
                     rotation: (vec2(mouse.0, mouse.1)
@@ -0,0 +1,157 @@
+use macroquad::prelude::*;
+
+use crate::{
+    assets::{Chunk, World},
+    enemy::{ENEMIES, Enemy, EnemyType},
+    player::Player,
+    settings::settings,
+    tile_entities::BARRIER,
+};
+
+pub struct Wave {
+    /// Seconds to wait after the previous wave is cleared
+    pub delay: f32,
+    pub enemies: Vec<(&'static EnemyType, u16)>,
+}
+enum RoomState {
+    Waiting,
+    Fighting {
+        next_wave: usize,
+        time: f32,
+        /// Ids of the enemies of the current wave
+        spawned: Vec<u32>,
+    },
+    Cleared,
+}
+/// An encounter authored as a rectangle in the map's "Rooms" object layer.
+/// Entering it closes its barriers, and they open again once every wave is beaten.
+pub struct Room {
+    pub rect: Rect,
+    pub waves: Vec<Wave>,
+    /// Barrier tiles from the "Interactable" layer in or right around the room
+    pub barriers: Vec<((i16, i16), i16)>,
+    state: RoomState,
+}
+impl Room {
+    pub fn new(rect: Rect, waves: &str, interactable: &[Chunk]) -> Self {
+        let mut barriers = Vec::new();
+        let area = Rect::new(rect.x - 16.0, rect.y - 16.0, rect.w + 32.0, rect.h + 32.0);
+        for chunk in interactable {
+            for (index, tile) in chunk.tiles.iter().enumerate() {
+                let tile = tile - 1;
+                let x = (index % 16) as i16 + chunk.x;
+                let y = (index / 16) as i16 + chunk.y;
+                if (81..=83).contains(&tile)
+                    && area.contains(vec2(x as f32 * 16.0 + 8.0, y as f32 * 16.0 + 8.0))
+                {
+                    barriers.push(((x, y), tile));
+                }
+            }
+        }
+        Self {
+            rect,
+            waves: parse_waves(waves),
+            barriers,
+            state: RoomState::Waiting,
+        }
+    }
+    pub fn update(
+        &mut self,
+        delta_time: f32,
+        player: &Player,
+        world: &mut World,
+        enemies: &mut Vec<Enemy>,
+    ) {
+        match &mut self.state {
+            RoomState::Waiting => {
+                if self.rect.contains(player.pos) && self.rect.contains(player.pos + 15.0) {
+                    for (pos, tile) in &self.barriers {
+                        world.tile_entities.insert(*pos, BARRIER.instantiate(*tile));
+                    }
+                    self.state = RoomState::Fighting {
+                        next_wave: 0,
+                        time: 0.0,
+                        spawned: Vec::new(),
+                    };
+                }
+            }
+            RoomState::Fighting {
+                next_wave,
+                time,
+                spawned,
+            } => {
+                if enemies.iter().any(|f| spawned.contains(&f.id)) {
+                    return;
+                }
+                let Some(wave) = self.waves.get(*next_wave) else {
+                    for (pos, _) in &self.barriers {
+                        world.tile_entities.remove(pos);
+                    }
+                    self.state = RoomState::Cleared;
+                    return;
+                };
+                *time += delta_time;
+                if *time < wave.delay {
+                    return;
+                }
+                spawned.clear();
+                for (ty, amount) in &wave.enemies {
+                    for _ in 0..settings().spawn_count(*amount as u32) {
+                        let mut enemy = Enemy::new(ty, spawn_position(self.rect, player, world));
+                        enemy.roll_elite(world.elite_chance);
+                        enemy.hear(player.pos + 8.0);
+                        spawned.push(enemy.id);
+                        enemies.push(enemy);
+                    }
+                }
+                *next_wave += 1;
+                *time = 0.0;
+            }
+            RoomState::Cleared => {}
+        }
+    }
+}
+
+/// Picks the center of an open tile in a room away from the player, or the middle of the room if
+/// none is found
+fn spawn_position(rect: Rect, player: &Player, world: &World) -> Vec2 {
+    for _ in 0..32 {
+        let x = (rand::gen_range(rect.left(), rect.right()) / 16.0).floor();
+        let y = (rand::gen_range(rect.top(), rect.bottom()) / 16.0).floor();
+        let pos = vec2(x, y) * 16.0 + 8.0;
+        if !world.is_solid(pos) && pos.distance(player.pos) > 48.0 {
+            return pos;
+        }
+    }
+    rect.center()
+}
+
+/// Parses waves written like `dog*3; 2: greeno*2, shooter`. Waves are separated by `;`,
+/// can start with a delay in seconds, and list enemy names with an optional count. Empty waves
+/// and entries are skipped, so a missing property or trailing separator is fine.
+fn parse_waves(source: &str) -> Vec<Wave> {
+    source
+        .split(';')
+        .filter(|wave| !wave.trim().is_empty())
+        .map(|wave| {
+            let (delay, wave) = match wave.split_once(':') {
+                Some((delay, wave)) => (delay.trim().parse().unwrap(), wave),
+                None => (0.0, wave),
+            };
+            let enemies = wave
+                .split(',')
+                .filter(|enemy| !enemy.trim().is_empty())
+                .map(|enemy| {
+                    let (name, amount) = enemy.split_once('*').unwrap_or((enemy, "1"));
+                    let name = name.trim();
+                    let ty = ENEMIES
+                        .iter()
+                        .find(|f| f.name == name)
+                        .unwrap_or_else(|| panic!("no enemy named {name}"));
+                    (ty, amount.trim().parse().unwrap())
+                })
+                .collect();
+            Wave { delay, enemies }
+        })
+        .collect()
+}
@@ -0,0 +1,93 @@
+use std::sync::RwLock;
+
+use macroquad::rand;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+        }
+    }
+    /// Multipliers for enemy health, enemy damage, hostile projectile speed and spawn counts
+    fn scaling(&self) -> (f32, f32, f32, f32) {
+        match self {
+            Difficulty::Easy => (0.75, 0.6, 0.85, 0.75),
+            Difficulty::Normal => (1.0, 1.0, 1.0, 1.0),
+            Difficulty::Hard => (1.4, 1.5, 1.2, 1.5),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    /// Hostile projectiles move at 70% speed
+    pub slower_bullets: bool,
+    /// The player takes half damage
+    pub damage_reduction: bool,
+    /// The player takes no damage at all, for testing
+    pub invincible: bool,
+    /// Generated stations are saved next to the game, to be opened in Tiled
+    pub export_generated: bool,
+}
+
+static SETTINGS: RwLock<Settings> = RwLock::new(Settings {
+    difficulty: Difficulty::Normal,
+    slower_bullets: false,
+    damage_reduction: false,
+    invincible: false,
+    export_generated: false,
+});
+
+pub fn settings() -> Settings {
+    *SETTINGS.read().unwrap()
+}
+pub fn set_settings(settings: Settings) {
+    *SETTINGS.write().unwrap() = settings;
+}
+
+impl Settings {
+    /// Damage the player takes from a hit of the given amount, after the assist options
+    pub fn damage_to_player(&self, amount: f32) -> f32 {
+        if self.invincible {
+            return 0.0;
+        }
+        if self.damage_reduction {
+            amount * 0.5
+        } else {
+            amount
+        }
+    }
+    /// Scales the health of an enemy as it spawns
+    pub fn enemy_health(&self, health: f32) -> f32 {
+        health * self.difficulty.scaling().0
+    }
+    /// Scales damage dealt by enemies and hostile projectiles
+    pub fn enemy_damage(&self, amount: f32) -> f32 {
+        amount * self.difficulty.scaling().1
+    }
+    pub fn hostile_projectile_speed(&self, speed: f32) -> f32 {
+        let speed = speed * self.difficulty.scaling().2;
+        if self.slower_bullets {
+            speed * 0.7
+        } else {
+            speed
+        }
+    }
+    /// Scales a number of enemies to spawn, rounding randomly so that fractions still add up.
+    /// Spawns at least one if any were asked for.
+    pub fn spawn_count(&self, count: u32) -> u32 {
+        let scaled = count as f32 * self.difficulty.scaling().3;
+        let extra = rand::gen_range(0.0, 1.0) < scaled.fract();
+        (scaled as u32 + extra as u32).max(count.min(1))
+    }
+}
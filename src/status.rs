@@ -0,0 +1,166 @@
+use macroquad::prelude::*;
+
+/// What dealt some damage, which enemies can be resistant to
+#[derive(Clone, Copy, PartialEq)]
+pub enum DamageType {
+    Energy,
+    Acid,
+    Fire,
+    Explosive,
+    Melee,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StatusKind {
+    Burn,
+    Slow,
+    Stun,
+    Poison,
+    Freeze,
+}
+impl StatusKind {
+    /// Damage per second dealt while it lasts, and its type
+    pub fn damage_per_second(&self) -> Option<(f32, DamageType)> {
+        match self {
+            StatusKind::Burn => Some((4.0, DamageType::Fire)),
+            StatusKind::Poison => Some((3.0, DamageType::Acid)),
+            _ => None,
+        }
+    }
+    /// How much of its movement speed something keeps while it lasts
+    pub fn speed(&self) -> f32 {
+        match self {
+            StatusKind::Slow => 0.5,
+            StatusKind::Stun | StatusKind::Freeze => 0.0,
+            _ => 1.0,
+        }
+    }
+    /// Tint of the sprite of something suffering from it
+    pub fn color(&self) -> Color {
+        match self {
+            StatusKind::Burn => Color::from_hex(0xff9a5a),
+            StatusKind::Slow => Color::from_hex(0xb08cff),
+            StatusKind::Stun => Color::from_hex(0xfff080),
+            StatusKind::Poison => Color::from_hex(0x9ae060),
+            StatusKind::Freeze => Color::from_hex(0x9ad8ff),
+        }
+    }
+    /// Draws the icon of this status, centered on `pos`, with each pixel `scale` wide
+    pub fn draw_icon(&self, pos: Vec2, scale: f32) {
+        let rect = |x: f32, y: f32, w: f32, h: f32, color: Color| {
+            draw_rectangle(
+                pos.x + x * scale,
+                pos.y + y * scale,
+                w * scale,
+                h * scale,
+                color,
+            );
+        };
+        let color = self.color();
+        rect(-5.0, -5.0, 10.0, 10.0, BLACK);
+        match self {
+            StatusKind::Burn => {
+                rect(-3.0, 0.0, 6.0, 4.0, color);
+                rect(-2.0, -2.0, 4.0, 2.0, color);
+                rect(-1.0, -4.0, 2.0, 2.0, color);
+                rect(-1.0, 1.0, 2.0, 3.0, WHITE);
+            }
+            StatusKind::Slow => {
+                rect(-3.0, -4.0, 6.0, 1.0, color);
+                rect(-3.0, 3.0, 6.0, 1.0, color);
+                rect(-2.0, -3.0, 4.0, 2.0, color);
+                rect(-1.0, -1.0, 2.0, 2.0, color);
+                rect(-2.0, 1.0, 4.0, 2.0, color);
+            }
+            StatusKind::Stun => {
+                rect(0.0, -4.0, 3.0, 1.0, color);
+                rect(-1.0, -3.0, 2.0, 3.0, color);
+                rect(-3.0, 0.0, 6.0, 1.0, color);
+                rect(0.0, 1.0, 2.0, 3.0, color);
+            }
+            StatusKind::Poison => {
+                rect(-1.0, -4.0, 2.0, 2.0, color);
+                rect(-2.0, -2.0, 4.0, 2.0, color);
+                rect(-3.0, 0.0, 6.0, 3.0, color);
+                rect(-2.0, 3.0, 4.0, 1.0, color);
+            }
+            StatusKind::Freeze => {
+                rect(-1.0, -4.0, 2.0, 8.0, color);
+                rect(-4.0, -1.0, 8.0, 2.0, color);
+                rect(-3.0, -3.0, 1.0, 1.0, color);
+                rect(2.0, -3.0, 1.0, 1.0, color);
+                rect(-3.0, 2.0, 1.0, 1.0, color);
+                rect(2.0, 2.0, 1.0, 1.0, color);
+            }
+        }
+    }
+}
+
+/// A status effect to inflict, and how many seconds it lasts
+#[derive(Clone, Copy, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub duration: f32,
+}
+
+/// Damage of a type, which can also inflict a status effect
+#[derive(Clone, Copy)]
+pub struct Damage {
+    pub amount: f32,
+    pub ty: DamageType,
+    pub effect: Option<StatusEffect>,
+}
+impl Damage {
+    pub const fn new(amount: f32, ty: DamageType) -> Self {
+        Self {
+            amount,
+            ty,
+            effect: None,
+        }
+    }
+    pub const fn with(self, kind: StatusKind, duration: f32) -> Self {
+        Self {
+            effect: Some(StatusEffect { kind, duration }),
+            ..self
+        }
+    }
+}
+
+/// Status effects something is suffering from, along with the time each has left
+#[derive(Default)]
+pub struct Statuses {
+    pub effects: Vec<(StatusEffect, f32)>,
+}
+impl Statuses {
+    /// Starts an effect, or restarts it if it is already running
+    pub fn apply(&mut self, effect: StatusEffect) {
+        self.effects.retain(|(f, _)| f.kind != effect.kind);
+        self.effects.push((effect, effect.duration));
+    }
+    /// Counts the effects down, returning the damage they dealt over this frame
+    pub fn update(&mut self, delta_time: f32) -> Vec<(f32, DamageType)> {
+        let mut damage = Vec::new();
+        for (effect, time) in self.effects.iter_mut() {
+            *time -= delta_time;
+            if let Some((amount, ty)) = effect.kind.damage_per_second() {
+                damage.push((amount * delta_time, ty));
+            }
+        }
+        self.effects.retain(|(_, time)| *time > 0.0);
+        damage
+    }
+    pub fn speed(&self) -> f32 {
+        self.effects
+            .iter()
+            .map(|(f, _)| f.kind.speed())
+            .fold(1.0, f32::min)
+    }
+    /// Whether it can't move or act at all
+    pub fn stunned(&self) -> bool {
+        self.speed() == 0.0
+    }
+    /// Color to draw the sprite with, from the most recent effect
+    pub fn tint(&self) -> Color {
+        self.effects.last().map_or(WHITE, |(f, _)| f.kind.color())
+    }
+}
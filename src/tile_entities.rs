@@ -0,0 +1,947 @@
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::PI,
+};
+
+use macroquad::prelude::*;
+
+use crate::{
+    assets::{Assets, Chunk, TmxObject, World},
+    boss::BOSSES,
+    enemy::{ENEMIES, Enemy, spawn_placed},
+    particles::{
+        BREACH_DUST, CRATE_DEBRIS, ELECTRIC_ARCS, EmitterType, FLAMES, MUZZLE_FLASH, Particles,
+        VENT_DEBRIS,
+    },
+    pickups::Keycard,
+    player::{ENERGY_SHOT, Player, Projectile},
+    status::{Damage, DamageType, StatusKind},
+};
+
+/// How close the player's center has to be to a tile entity's center to interact with it
+const INTERACT_DISTANCE: f32 = 24.0;
+/// How far an open breach pulls things in from
+pub const BREACH_RANGE: f32 = 112.0;
+
+/// What should happen to a tile entity after one of its hooks ran
+pub enum TileEntityUpdateResult {
+    None,
+    Remove,
+    /// Turns every tile entity placed by one of the target objects on or off
+    Signal {
+        targets: Vec<u32>,
+        on: bool,
+    },
+}
+/// Everything a tile entity hook is allowed to change
+pub struct TileEntityContext<'a> {
+    pub delta_time: f32,
+    pub player: &'a mut Player,
+    pub enemies: &'a mut Vec<Enemy>,
+    pub projectiles: &'a mut Vec<Projectile>,
+    pub particles: &'a mut Particles,
+    /// The world, without its tile entities while they update
+    pub world: &'a World,
+    /// Tiles of the tile entities that block movement and sight, since they're missing from `world`
+    pub blocking: &'a HashSet<(i16, i16)>,
+}
+/// Called with the entity, the world position of its tile and the context
+type TileEntityHook = fn(&mut TileEntity, Vec2, &mut TileEntityContext) -> TileEntityUpdateResult;
+/// Text to show when the player can interact with a tile entity, if they can right now
+type TileEntityTooltip = fn(&TileEntity, &Player) -> Option<&'static str>;
+
+/// How much damage a tile takes from projectiles and explosions before it breaks
+#[derive(Clone, Copy)]
+pub struct Destructible {
+    pub health: f32,
+    pub debris: &'static EmitterType,
+}
+
+pub struct TileEntityKind {
+    /// Object type that places this kind from the "Entities" layer
+    pub name: &'static str,
+    /// Tiles that place this kind from the "TileEntities" layer
+    pub tiles: &'static [i16],
+    /// Tile for objects that don't set a `tile` property
+    pub tile: i16,
+    pub collision: bool,
+    pub draw: fn(&TileEntity, &Assets, Vec2),
+    pub update: Option<TileEntityHook>,
+    /// Tooltip and hook for pressing E next to it
+    pub interact: Option<(TileEntityTooltip, TileEntityHook)>,
+    /// Runs once whenever the player steps onto its tile
+    pub on_enter: Option<TileEntityHook>,
+    /// Runs when a switch wired to it turns on or off
+    pub on_signal: Option<fn(&mut TileEntity, Vec2, bool, &mut TileEntityContext)>,
+    /// Runs while a projectile is inside its tile, returning whether the projectile is stopped
+    pub on_projectile_hit: Option<fn(&mut TileEntity, &Projectile, &mut Particles) -> bool>,
+    pub destructible: Option<Destructible>,
+}
+impl TileEntityKind {
+    pub fn instantiate(&'static self, tile_index: i16) -> TileEntity {
+        TileEntity {
+            kind: self,
+            collision: self.collision,
+            enabled: true,
+            active: false,
+            tile_index,
+            health: self.destructible.map_or(0.0, |f| f.health),
+            id: None,
+            properties: HashMap::new(),
+            timer: 0.0,
+            direction: Vec2::X,
+            occupied: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TileEntity {
+    pub kind: &'static TileEntityKind,
+    pub collision: bool,
+    pub enabled: bool,
+    /// Whether a switch is on, a breach is sealed, an electric floor is live or a turret is hacked
+    pub active: bool,
+    pub tile_index: i16,
+    pub health: f32,
+    /// Id of the "Entities" object or the wiring that placed it, for other objects to link to
+    pub id: Option<u32>,
+    pub properties: HashMap<String, String>,
+    /// Free for the kind's hooks to count time with
+    pub timer: f32,
+    /// Where a turret is aiming
+    pub direction: Vec2,
+    /// Whether the player was on its tile last frame
+    occupied: bool,
+}
+
+fn draw_tile(this: &TileEntity, assets: &Assets, pos: Vec2) {
+    assets.tileset.draw_tile(
+        pos.x,
+        pos.y,
+        (this.tile_index % 16) as f32,
+        (this.tile_index / 16) as f32,
+        None,
+    );
+}
+
+pub static BARRIER: TileEntityKind = TileEntityKind {
+    name: "barrier",
+    tiles: &[81, 82, 83],
+    tile: 81,
+    collision: true,
+    draw: draw_tile,
+    update: None,
+    interact: None,
+    on_enter: None,
+    on_signal: None,
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// Wire from the "Interactable" layer, which sets off the rest of its wiring when the player steps
+/// onto it
+pub static WIRE: TileEntityKind = TileEntityKind {
+    name: "wire",
+    tiles: &[],
+    tile: 32,
+    collision: false,
+    draw: |_, _, _| {},
+    update: None,
+    interact: None,
+    on_enter: Some(|this, _, _| TileEntityUpdateResult::Signal {
+        targets: this.id.into_iter().collect(),
+        on: true,
+    }),
+    on_signal: None,
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// Spawns the enemy for its tile the first time its wiring is set off
+pub static SPAWNER: TileEntityKind = TileEntityKind {
+    name: "spawner",
+    tiles: &[],
+    tile: 96,
+    collision: false,
+    draw: |_, _, _| {},
+    update: None,
+    interact: None,
+    on_enter: None,
+    on_signal: Some(|this, pos, _, context| {
+        if !this.enabled {
+            return;
+        }
+        this.enabled = false;
+        // tiles past the last enemy are reserved for ones that don't exist yet
+        if let Some(ty) = ENEMIES.get(this.tile_index as usize - 96) {
+            let hear_from = context.player.pos + 8.0;
+            let mut enemies = spawn_placed(ty, pos + 8.0, context.world, hear_from);
+            context.enemies.append(&mut enemies);
+        }
+    }),
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// Spawns the boss for its tile the first time its wiring is set off, which locks the wiring's
+/// barriers until the boss dies
+pub static BOSS_SPAWNER: TileEntityKind = TileEntityKind {
+    name: "boss_spawner",
+    tiles: &[],
+    tile: 135,
+    collision: false,
+    draw: |_, _, _| {},
+    update: None,
+    interact: None,
+    on_enter: None,
+    on_signal: Some(|this, pos, _, context| {
+        if !this.enabled {
+            return;
+        }
+        this.enabled = false;
+        // tiles past the last boss are reserved for ones that don't exist yet
+        if let Some(ty) = BOSSES.get(this.tile_index as usize - 135) {
+            let mut enemy = Enemy::new_boss(ty, pos + 8.0);
+            if let Some(boss) = &mut enemy.boss {
+                boss.arena = this.id;
+            }
+            context.enemies.push(enemy);
+        }
+    }),
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// A barrier from the "Interactable" layer that is open until its wiring is set off
+pub static LOCKDOWN: TileEntityKind = TileEntityKind {
+    name: "lockdown",
+    tiles: &[],
+    tile: 81,
+    collision: true,
+    draw: |this, assets, pos| {
+        if this.enabled {
+            draw_tile(this, assets, pos);
+        }
+    },
+    update: None,
+    interact: None,
+    on_enter: None,
+    on_signal: Some(|this, _, on, _| this.enabled |= on),
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// A barrier that opens when its wiring is set off, once the player has a weapon and every enemy
+/// is dead
+pub static OPENER: TileEntityKind = TileEntityKind {
+    name: "opener",
+    tiles: &[],
+    tile: 81,
+    collision: true,
+    draw: |this, assets, pos| {
+        if this.enabled {
+            draw_tile(this, assets, pos);
+        }
+    },
+    update: None,
+    interact: None,
+    on_enter: None,
+    on_signal: Some(|this, _, on, context| {
+        if on && context.enemies.is_empty() && !context.player.inventory.slots.is_empty() {
+            this.enabled = false;
+        }
+    }),
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// A barrier that opens and closes as a whole. Locked by a `keycard` colour, which the door takes
+/// if `consume` is set, or only moved by switches if `wired` is set.
+pub static DOOR: TileEntityKind = TileEntityKind {
+    name: "door",
+    tiles: &[],
+    tile: 81,
+    collision: true,
+    draw: |this, assets, pos| {
+        if !this.enabled {
+            return;
+        }
+        draw_tile(this, assets, pos);
+        if let Some(keycard) = door_keycard(this) {
+            draw_rectangle_lines(pos.x, pos.y, 16.0, 16.0, 2.0, keycard.color());
+        }
+    },
+    update: None,
+    interact: Some((
+        |this, player| {
+            if this.properties.get("wired").is_some_and(|f| f == "true") {
+                None
+            } else if let Some(keycard) = door_keycard(this)
+                && !player.keycards.contains(&keycard)
+            {
+                Some(keycard.locked_tooltip())
+            } else if this.enabled {
+                Some("E: Open door")
+            } else {
+                Some("E: Close door")
+            }
+        },
+        |this, _, context| {
+            if let Some(keycard) = door_keycard(this) {
+                let Some(index) = context.player.keycards.iter().position(|f| *f == keycard) else {
+                    return TileEntityUpdateResult::None;
+                };
+                if this.properties.get("consume").is_some_and(|f| f == "true") {
+                    context.player.keycards.remove(index);
+                }
+            }
+            TileEntityUpdateResult::Signal {
+                targets: this.id.into_iter().collect(),
+                on: this.enabled,
+            }
+        },
+    )),
+    on_enter: None,
+    on_signal: Some(|this, pos, on, context| {
+        let blocked = Rect::new(pos.x, pos.y, 16.0, 16.0).contains(context.player.pos + 8.0);
+        // don't close on top of the player
+        if on || !blocked {
+            this.enabled = !on;
+        }
+        // once opened, a door stays unlocked
+        if on {
+            this.properties.remove("keycard");
+        }
+    }),
+    on_projectile_hit: Some(|this, _, _| this.enabled),
+    destructible: None,
+};
+/// A floor plate that is on while the player or an enemy stands on it
+pub static FLOOR_SWITCH: TileEntityKind = TileEntityKind {
+    name: "floor_switch",
+    tiles: &[],
+    tile: 0,
+    collision: false,
+    draw: |this, _, pos| {
+        draw_rectangle(pos.x + 2.0, pos.y + 2.0, 12.0, 12.0, SWITCH_BASE_COLOR);
+        let color = if this.active {
+            SWITCH_ON_COLOR
+        } else {
+            SWITCH_OFF_COLOR
+        };
+        let inset = if this.active { 5.0 } else { 4.0 };
+        draw_rectangle(
+            pos.x + inset,
+            pos.y + inset,
+            16.0 - inset * 2.0,
+            16.0 - inset * 2.0,
+            color,
+        );
+    },
+    update: Some(|this, pos, context| {
+        let rect = Rect::new(pos.x, pos.y, 16.0, 16.0);
+        let pressed = rect.contains(context.player.pos + 8.0)
+            || context.enemies.iter().any(|f| rect.contains(f.pos));
+        if pressed == this.active {
+            return TileEntityUpdateResult::None;
+        }
+        this.active = pressed;
+        TileEntityUpdateResult::Signal {
+            targets: switch_targets(this),
+            on: pressed,
+        }
+    }),
+    interact: None,
+    on_enter: None,
+    on_signal: None,
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// A lever that the player flips between on and off
+pub static LEVER: TileEntityKind = TileEntityKind {
+    name: "lever",
+    tiles: &[],
+    tile: 0,
+    collision: false,
+    draw: |this, _, pos| {
+        let base = pos + vec2(8.0, 12.0);
+        let tilt = if this.active { 4.0 } else { -4.0 };
+        let color = if this.active {
+            SWITCH_ON_COLOR
+        } else {
+            SWITCH_OFF_COLOR
+        };
+        draw_line(
+            base.x,
+            base.y,
+            base.x + tilt,
+            base.y - 8.0,
+            2.0,
+            SWITCH_BASE_COLOR,
+        );
+        draw_rectangle(base.x + tilt - 1.5, base.y - 10.0, 3.0, 3.0, color);
+        draw_rectangle(base.x - 4.0, base.y - 1.0, 8.0, 4.0, SWITCH_BASE_COLOR);
+    },
+    update: None,
+    interact: Some((
+        |_, _| Some("E: Pull lever"),
+        |this, _, _| {
+            this.active = !this.active;
+            TileEntityUpdateResult::Signal {
+                targets: switch_targets(this),
+                on: this.active,
+            }
+        },
+    )),
+    on_enter: None,
+    on_signal: None,
+    on_projectile_hit: None,
+    destructible: None,
+};
+
+/// A wooden crate that blocks the way until it is shot apart
+pub static CRATE: TileEntityKind = TileEntityKind {
+    name: "crate",
+    tiles: &[],
+    tile: 0,
+    collision: true,
+    draw: |this, _, pos| {
+        draw_rectangle(pos.x + 1.0, pos.y + 1.0, 14.0, 14.0, CRATE_EDGE_COLOR);
+        draw_rectangle(pos.x + 2.0, pos.y + 2.0, 12.0, 12.0, CRATE_COLOR);
+        draw_line(
+            pos.x + 2.0,
+            pos.y + 14.0,
+            pos.x + 14.0,
+            pos.y + 2.0,
+            1.0,
+            CRATE_EDGE_COLOR,
+        );
+        if this.health < CRATE_HEALTH / 2.0 {
+            draw_line(
+                pos.x + 4.0,
+                pos.y + 3.0,
+                pos.x + 7.0,
+                pos.y + 7.0,
+                1.0,
+                BLACK,
+            );
+        }
+    },
+    update: None,
+    interact: None,
+    on_enter: None,
+    on_signal: None,
+    on_projectile_hit: None,
+    destructible: Some(Destructible {
+        health: CRATE_HEALTH,
+        debris: &CRATE_DEBRIS,
+    }),
+};
+/// A vent cover over a gap in the wall, which opens up a shortcut once broken
+pub static VENT: TileEntityKind = TileEntityKind {
+    name: "vent",
+    tiles: &[],
+    tile: 0,
+    collision: true,
+    draw: |_, _, pos| {
+        draw_rectangle(pos.x + 1.0, pos.y + 1.0, 14.0, 14.0, SWITCH_BASE_COLOR);
+        for y in [4.0, 7.0, 10.0, 13.0] {
+            draw_line(
+                pos.x + 3.0,
+                pos.y + y,
+                pos.x + 13.0,
+                pos.y + y,
+                1.0,
+                VENT_COLOR,
+            );
+        }
+    },
+    update: None,
+    interact: None,
+    on_enter: None,
+    on_signal: None,
+    on_projectile_hit: None,
+    destructible: Some(Destructible {
+        health: 8.0,
+        debris: &VENT_DEBRIS,
+    }),
+};
+/// A hole in the hull that pulls everything nearby out into space until the player seals it
+pub static BREACH: TileEntityKind = TileEntityKind {
+    name: "breach",
+    tiles: &[],
+    tile: 0,
+    collision: false,
+    draw: |this, _, pos| {
+        if this.active {
+            draw_rectangle(pos.x, pos.y, 16.0, 16.0, SWITCH_BASE_COLOR);
+            for (x, y) in [(2.0, 2.0), (13.0, 2.0), (2.0, 13.0), (13.0, 13.0)] {
+                draw_rectangle(pos.x + x, pos.y + y, 1.0, 1.0, VENT_COLOR);
+            }
+        }
+    },
+    update: Some(|this, pos, context| {
+        if !this.active {
+            let offset = Vec2::from_angle(rand::gen_range(0.0, 2.0 * PI)) * BREACH_RANGE / 2.0;
+            context.particles.emit(
+                &BREACH_DUST,
+                pos + 8.0 + offset,
+                -offset,
+                context.delta_time,
+            );
+        }
+        TileEntityUpdateResult::None
+    }),
+    interact: Some((
+        |this, _| (!this.active).then_some("E: Seal breach"),
+        |this, pos, context| {
+            // patch it from the side, so the player isn't sealed inside the wall
+            let player = Rect::new(context.player.pos.x, context.player.pos.y, 16.0, 16.0);
+            if !Rect::new(pos.x, pos.y, 16.0, 16.0).overlaps(&player) {
+                this.active = true;
+                this.collision = true;
+            }
+            TileEntityUpdateResult::None
+        },
+    )),
+    on_enter: None,
+    on_signal: None,
+    on_projectile_hit: Some(|this, _, _| this.active),
+    destructible: None,
+};
+/// A floor panel that is live for `on` seconds out of every `off` + `on`, hurting anything standing
+/// on it. Switches wired to it cut its power while they're on.
+pub static ELECTRIC_FLOOR: TileEntityKind = TileEntityKind {
+    name: "electric_floor",
+    tiles: &[],
+    tile: 0,
+    collision: false,
+    draw: |this, _, pos| {
+        draw_rectangle(pos.x + 1.0, pos.y + 1.0, 14.0, 14.0, SWITCH_BASE_COLOR);
+        draw_rectangle_lines(pos.x + 3.0, pos.y + 3.0, 10.0, 10.0, 1.0, VENT_COLOR);
+        let off = property_f32(this, "off", ELECTRIC_OFF_TIME);
+        if this.active {
+            // three frames of arcs across the panel
+            let frame = (this.timer / HAZARD_FRAME_TIME) as usize % 3;
+            let mut last = pos + vec2(1.0, 8.0);
+            for (index, x) in [4.0, 7.0, 10.0, 13.0, 15.0].into_iter().enumerate() {
+                let y = 8.0 + [-4.0, 3.0, -2.0, 4.0][(index + frame) % 4];
+                let next = pos + vec2(x, y);
+                draw_line(last.x, last.y, next.x, next.y, 1.0, ELECTRIC_COLOR);
+                last = next;
+            }
+        } else if this.enabled && off - this.timer < ELECTRIC_WARNING && this.timer % 0.2 < 0.1 {
+            draw_rectangle_lines(pos.x + 3.0, pos.y + 3.0, 10.0, 10.0, 1.0, ELECTRIC_COLOR);
+        }
+    },
+    update: Some(|this, pos, context| {
+        let off = property_f32(this, "off", ELECTRIC_OFF_TIME);
+        let on = property_f32(this, "on", ELECTRIC_ON_TIME);
+        this.timer = (this.timer + context.delta_time) % (off + on);
+        this.active = this.enabled && this.timer >= off;
+        if this.active {
+            context
+                .particles
+                .emit(&ELECTRIC_ARCS, pos + 8.0, Vec2::ZERO, context.delta_time);
+            hurt_standing(pos, ELECTRIC_DAMAGE, context);
+        }
+        TileEntityUpdateResult::None
+    }),
+    interact: None,
+    on_enter: None,
+    on_signal: Some(|this, _, on, _| this.enabled = !on),
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// A patch of burning floor
+pub static FIRE: TileEntityKind = TileEntityKind {
+    name: "fire",
+    tiles: &[],
+    tile: 0,
+    collision: false,
+    draw: |this, _, pos| {
+        for index in 0..3 {
+            let x = pos.x + 3.0 + index as f32 * 5.0;
+            let height = 7.0 + 3.0 * (this.timer * 9.0 + index as f32 * 2.1).sin();
+            let base = pos.y + 15.0;
+            draw_triangle(
+                vec2(x - 3.0, base),
+                vec2(x + 3.0, base),
+                vec2(x, base - height),
+                FIRE_COLOR,
+            );
+            draw_triangle(
+                vec2(x - 1.5, base),
+                vec2(x + 1.5, base),
+                vec2(x, base - height / 2.0),
+                FIRE_CORE_COLOR,
+            );
+        }
+    },
+    update: Some(|this, pos, context| {
+        this.timer += context.delta_time;
+        let spawn = pos + vec2(rand::gen_range(2.0, 14.0), 12.0);
+        context
+            .particles
+            .emit(&FLAMES, spawn, -Vec2::Y, context.delta_time);
+        hurt_standing(pos, FIRE_DAMAGE, context);
+        TileEntityUpdateResult::None
+    }),
+    interact: None,
+    on_enter: None,
+    on_signal: None,
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// A puddle of acid left behind by dying greenos, which dries up after a while
+pub static ACID: TileEntityKind = TileEntityKind {
+    name: "acid",
+    tiles: &[],
+    tile: 0,
+    collision: false,
+    draw: |this, _, pos| {
+        let fade = ((ACID_LIFETIME - this.timer) / ACID_FADE_TIME).clamp(0.0, 1.0);
+        let color = Color {
+            a: ACID_COLOR.a * fade,
+            ..ACID_COLOR
+        };
+        draw_circle(pos.x + 6.0, pos.y + 9.0, 5.0, color);
+        draw_circle(pos.x + 10.0, pos.y + 7.0, 4.5, color);
+        // a bubble pops every few frames
+        let frame = (this.timer / HAZARD_FRAME_TIME) as usize % 4;
+        if frame < 2 {
+            let bubble = [vec2(5.0, 8.0), vec2(10.0, 6.0)][(this.timer / 0.6) as usize % 2];
+            draw_circle_lines(
+                pos.x + bubble.x,
+                pos.y + bubble.y,
+                1.0 + frame as f32,
+                1.0,
+                Color { a: fade, ..WHITE },
+            );
+        }
+    },
+    update: Some(|this, pos, context| {
+        this.timer += context.delta_time;
+        if this.timer >= ACID_LIFETIME {
+            return TileEntityUpdateResult::Remove;
+        }
+        hurt_standing(pos, ACID_DAMAGE, context);
+        TileEntityUpdateResult::None
+    }),
+    interact: None,
+    on_enter: None,
+    on_signal: None,
+    on_projectile_hit: None,
+    destructible: None,
+};
+/// An automatic turret that shoots at the player, or at enemies once the player hacks it
+pub static TURRET: TileEntityKind = TileEntityKind {
+    name: "turret",
+    tiles: &[],
+    tile: 0,
+    collision: true,
+    draw: |this, _, pos| {
+        let center = pos + 8.0;
+        let tip = center + this.direction * 7.0;
+        draw_rectangle(pos.x + 2.0, pos.y + 2.0, 12.0, 12.0, SWITCH_BASE_COLOR);
+        draw_line(center.x, center.y, tip.x, tip.y, 3.0, VENT_COLOR);
+        let color = if this.active {
+            SWITCH_ON_COLOR
+        } else {
+            SWITCH_OFF_COLOR
+        };
+        draw_circle(center.x, center.y, 3.0, color);
+    },
+    update: Some(|this, pos, context| {
+        let center = pos + 8.0;
+        let in_sight = |target: &Vec2| {
+            target.distance(center) < TURRET_RANGE
+                && context
+                    .world
+                    .line_of_sight_with(center, *target, |f| context.blocking.contains(&f))
+        };
+        let target = if this.active {
+            context
+                .enemies
+                .iter()
+                .filter(|f| !f.emerging)
+                .map(|f| f.pos)
+                .filter(in_sight)
+                .min_by(|a, b| {
+                    a.distance_squared(center)
+                        .total_cmp(&b.distance_squared(center))
+                })
+        } else {
+            Some(context.player.pos + 8.0).filter(in_sight)
+        };
+        this.timer -= context.delta_time;
+        if let Some(target) = target {
+            this.direction = (target - center).normalize_or(this.direction);
+            if this.timer <= 0.0 {
+                this.timer = TURRET_FIRE_DELAY;
+                let muzzle = center + this.direction * 12.0;
+                context.projectiles.push(Projectile::new(
+                    &ENERGY_SHOT,
+                    muzzle,
+                    this.direction,
+                    this.active,
+                ));
+                context
+                    .particles
+                    .emit(&MUZZLE_FLASH, muzzle, this.direction, 0.0);
+            }
+        }
+        TileEntityUpdateResult::None
+    }),
+    interact: Some((
+        |this, _| (!this.active).then_some("E: Hack turret"),
+        |this, _, _| {
+            this.active = true;
+            TileEntityUpdateResult::None
+        },
+    )),
+    on_enter: None,
+    on_signal: None,
+    on_projectile_hit: None,
+    destructible: Some(Destructible {
+        health: 20.0,
+        debris: &VENT_DEBRIS,
+    }),
+};
+
+const CRATE_HEALTH: f32 = 12.0;
+const CRATE_COLOR: Color = Color::from_hex(0x9a6a3a);
+const CRATE_EDGE_COLOR: Color = Color::from_hex(0x5a3a22);
+const VENT_COLOR: Color = Color::from_hex(0x6a6a74);
+const SWITCH_BASE_COLOR: Color = Color::from_hex(0x2b2b30);
+const SWITCH_ON_COLOR: Color = Color::from_hex(0x60d060);
+const SWITCH_OFF_COLOR: Color = Color::from_hex(0xd04040);
+/// How long each frame of a hazard's animation lasts
+const HAZARD_FRAME_TIME: f32 = 0.08;
+const ELECTRIC_COLOR: Color = Color::from_hex(0xd8f0ff);
+const ELECTRIC_OFF_TIME: f32 = 2.0;
+const ELECTRIC_ON_TIME: f32 = 1.0;
+/// How long before turning on an electric floor starts flashing
+const ELECTRIC_WARNING: f32 = 0.6;
+/// Damage per second to anything standing on a hazard
+const ELECTRIC_DAMAGE: Damage = Damage::new(30.0, DamageType::Energy).with(StatusKind::Slow, 0.5);
+const FIRE_DAMAGE: Damage = Damage::new(15.0, DamageType::Fire).with(StatusKind::Burn, 2.0);
+const ACID_DAMAGE: Damage = Damage::new(10.0, DamageType::Acid).with(StatusKind::Poison, 2.0);
+const FIRE_COLOR: Color = Color::from_hex(0xe76d46);
+const FIRE_CORE_COLOR: Color = Color::from_hex(0xffd27f);
+const ACID_COLOR: Color = Color::new(0.45, 0.8, 0.2, 0.8);
+const ACID_LIFETIME: f32 = 8.0;
+const ACID_FADE_TIME: f32 = 2.0;
+const TURRET_RANGE: f32 = 128.0;
+const TURRET_FIRE_DELAY: f32 = 0.5;
+
+fn door_keycard(door: &TileEntity) -> Option<Keycard> {
+    door.properties
+        .get("keycard")
+        .map(|f| Keycard::from_name(f))
+}
+/// Number in one of a tile entity's properties, or a default if it isn't set
+fn property_f32(entity: &TileEntity, name: &str, default: f32) -> f32 {
+    entity
+        .properties
+        .get(name)
+        .map_or(default, |f| f.parse().unwrap())
+}
+/// Hurts the player and every enemy standing on a tile, by an amount per second
+fn hurt_standing(pos: Vec2, damage: Damage, context: &mut TileEntityContext) {
+    let rect = Rect::new(pos.x, pos.y, 16.0, 16.0);
+    let damage = Damage {
+        amount: damage.amount * context.delta_time,
+        ..damage
+    };
+    if rect.contains(context.player.pos + 8.0) {
+        context.player.hit(damage);
+    }
+    for enemy in context
+        .enemies
+        .iter_mut()
+        .filter(|f| !f.emerging && rect.contains(f.pos))
+    {
+        enemy.hit(damage, false);
+    }
+}
+/// Object ids in the comma separated `targets` property of a switch
+fn switch_targets(switch: &TileEntity) -> Vec<u32> {
+    switch
+        .properties
+        .get("targets")
+        .map(|f| f.split(',').map(|f| f.trim().parse().unwrap()).collect())
+        .unwrap_or_default()
+}
+
+pub static TILE_ENTITY_KINDS: [&TileEntityKind; 16] = [
+    &BARRIER,
+    &WIRE,
+    &SPAWNER,
+    &BOSS_SPAWNER,
+    &LOCKDOWN,
+    &OPENER,
+    &DOOR,
+    &FLOOR_SWITCH,
+    &LEVER,
+    &CRATE,
+    &VENT,
+    &BREACH,
+    &ELECTRIC_FLOOR,
+    &FIRE,
+    &ACID,
+    &TURRET,
+];
+
+pub fn kind_for_tile(tile: i16) -> Option<&'static TileEntityKind> {
+    TILE_ENTITY_KINDS
+        .into_iter()
+        .find(|f| f.tiles.contains(&tile))
+}
+/// Places a tile entity on every tile covered by an "Entities" object, if its type is a kind
+pub fn place_object(tile_entities: &mut HashMap<(i16, i16), TileEntity>, object: &TmxObject) {
+    let Some(kind) = TILE_ENTITY_KINDS
+        .into_iter()
+        .find(|f| f.name == object.class)
+    else {
+        return;
+    };
+    let tile = object
+        .properties
+        .get("tile")
+        .map_or(kind.tile, |f| f.parse().unwrap());
+    let x1 = (object.x / 16.0).floor() as i16;
+    let y1 = (object.y / 16.0).floor() as i16;
+    let x2 = ((object.x + object.width) / 16.0).ceil() as i16;
+    let y2 = ((object.y + object.height) / 16.0).ceil() as i16;
+    for x in x1..x2.max(x1 + 1) {
+        for y in y1..y2.max(y1 + 1) {
+            let mut entity = kind.instantiate(tile);
+            entity.id = Some(object.id);
+            entity.properties = object.properties.clone();
+            tile_entities.insert((x, y), entity);
+        }
+    }
+}
+
+/// Places tile entities for every group of connected tiles in the "Interactable" layer with wire in
+/// it, giving each group its own id from `next_id` on
+pub fn place_wiring(
+    tile_entities: &mut HashMap<(i16, i16), TileEntity>,
+    interactable: &[Chunk],
+    mut next_id: u32,
+) {
+    let mut tiles = HashMap::new();
+    for chunk in interactable {
+        for (index, tile) in chunk.tiles.iter().enumerate() {
+            let x = (index % 16) as i16 + chunk.x;
+            let y = (index / 16) as i16 + chunk.y;
+            if *tile != 0 {
+                tiles.insert((x, y), tile - 1);
+            }
+        }
+    }
+    let mut checked = HashSet::new();
+    for &start in tiles.keys() {
+        if !checked.insert(start) {
+            continue;
+        }
+        let mut wiring = vec![start];
+        let mut index = 0;
+        while let Some(&(x, y)) = wiring.get(index) {
+            index += 1;
+            for pos in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                if tiles.contains_key(&pos) && checked.insert(pos) {
+                    wiring.push(pos);
+                }
+            }
+        }
+        if !wiring.iter().any(|f| tiles[f] == 32) {
+            continue;
+        }
+        for pos in wiring {
+            let tile = tiles[&pos];
+            let mut entity = match tile {
+                32 => WIRE.instantiate(tile),
+                96..112 => SPAWNER.instantiate(tile),
+                135..140 => BOSS_SPAWNER.instantiate(tile),
+                81..=83 => {
+                    let mut entity = LOCKDOWN.instantiate(tile);
+                    entity.enabled = false;
+                    entity
+                }
+                // openers take over the barrier they're placed on
+                64 => match tile_entities.get(&pos) {
+                    Some(barrier) => OPENER.instantiate(barrier.tile_index),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            entity.id = Some(next_id);
+            tile_entities.insert(pos, entity);
+        }
+        next_id += 1;
+    }
+}
+
+/// Runs the hooks of every tile entity, using the nearest one if `interact` is set, and returns the
+/// tooltip of the one the player can use
+pub fn update_tile_entities(
+    tile_entities: &mut HashMap<(i16, i16), TileEntity>,
+    context: &mut TileEntityContext,
+    interact: bool,
+) -> Option<&'static str> {
+    let center = context.player.pos + 8.0;
+    let player_tile = (
+        (center.x / 16.0).floor() as i16,
+        (center.y / 16.0).floor() as i16,
+    );
+    let tile_center = |(x, y): (i16, i16)| vec2(x as f32, y as f32) * 16.0 + 8.0;
+    let usable = tile_entities
+        .iter()
+        .filter_map(|(p, f)| Some((*p, f.kind.interact?.0(f, context.player)?)))
+        .filter(|(p, _)| tile_center(*p).distance(center) < INTERACT_DISTANCE)
+        .min_by(|(a, _), (b, _)| {
+            (tile_center(*a).distance_squared(center))
+                .total_cmp(&tile_center(*b).distance_squared(center))
+        });
+
+    let mut removed = Vec::new();
+    let mut signals = Vec::new();
+    for (&(x, y), entity) in tile_entities.iter_mut() {
+        let pos = vec2(x as f32, y as f32) * 16.0;
+        let mut results = Vec::new();
+        if let Some(update) = entity.kind.update {
+            results.push(update(entity, pos, context));
+        }
+        let inside = (x, y) == player_tile;
+        if inside
+            && !entity.occupied
+            && let Some(on_enter) = entity.kind.on_enter
+        {
+            results.push(on_enter(entity, pos, context));
+        }
+        entity.occupied = inside;
+        if interact
+            && usable.is_some_and(|f| f.0 == (x, y))
+            && let Some((_, action)) = entity.kind.interact
+        {
+            results.push(action(entity, pos, context));
+        }
+        for result in results {
+            match result {
+                TileEntityUpdateResult::None => {}
+                TileEntityUpdateResult::Remove => removed.push((x, y)),
+                TileEntityUpdateResult::Signal { targets, on } => signals.push((targets, on)),
+            }
+        }
+    }
+    for (targets, on) in signals {
+        for (&(x, y), entity) in tile_entities.iter_mut() {
+            if let Some(on_signal) = entity.kind.on_signal
+                && entity.id.is_some_and(|f| targets.contains(&f))
+            {
+                on_signal(entity, vec2(x as f32, y as f32) * 16.0, on, context);
+            }
+        }
+    }
+    for pos in removed {
+        tile_entities.remove(&pos);
+    }
+    usable.map(|f| f.1)
+}
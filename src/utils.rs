@@ -13,6 +13,10 @@ pub fn create_camera(w: f32, h: f32) -> Camera2D {
         ..Default::default()
     }
 }
+/// Multiplies two colours together, channel by channel
+pub fn multiply(a: Color, b: Color) -> Color {
+    Color::new(a.r * b.r, a.g * b.g, a.b * b.b, a.a * b.a)
+}
 pub fn get_input_axis() -> Vec2 {
     let mut i = Vec2::ZERO;
     if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {